@@ -0,0 +1,417 @@
+//! Macros for declaring device protocols.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Attribute, Error, Fields, Ident, ItemEnum, LitInt, Meta, Token, Variant,
+};
+
+/// Declares both directions of a device protocol in one enum.
+///
+/// Every variant is annotated with the direction it travels in
+/// and its opcode. Commands to the peripheral also list the
+/// responses that may answer them:
+///
+/// ```ignore
+/// #[bidirectional]
+/// #[to_peripheral(derive(Clone, Copy))]
+/// pub enum Protocol {
+///     #[to_peripheral(0x11, answer = PumpState | Fault)]
+///     Get,
+///     #[from_peripheral(0xaa)]
+///     PumpState(PumpState),
+///     #[from_peripheral(0x1f)]
+///     Fault(Fault),
+/// }
+/// ```
+///
+/// This generates the `ToPeripheral` and `FromPeripheral` enums
+/// (serializable, with the given opcodes as discriminants),
+/// `ToPeripheral::answered_by`, which tells whether a response
+/// is a valid answer to a command, and `MESSAGES`, a description
+/// of every message (see `common::command::description`).
+/// With the `std` feature of `common`, both enums also implement
+/// `Display` and `FromStr` (see `common::text`).
+///
+/// The name of the annotated enum is not used. Attributes
+/// in `#[to_peripheral(...)]` and `#[from_peripheral(...)]`
+/// on the enum apply to the respective generated enum only,
+/// all others apply to both.
+///
+/// The generated code refers to `crate::command::description`
+/// and `crate::text`, so the macro is only meant to be used inside `common`.
+#[proc_macro_attribute]
+pub fn bidirectional(args: TokenStream, item: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return Error::new(Span::call_site(), "`bidirectional` takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    let item = parse_macro_input!(item as ItemEnum);
+
+    expand(item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+const TO: &str = "to_peripheral";
+const FROM: &str = "from_peripheral";
+
+/// The annotation on a variant.
+enum Direction {
+    To { opcode: LitInt, answers: Vec<Ident> },
+    From { opcode: LitInt },
+}
+
+impl Direction {
+    fn opcode(&self) -> &LitInt {
+        match self {
+            Self::To { opcode, .. } | Self::From { opcode } => opcode,
+        }
+    }
+}
+
+/// `0xca, answer = PumpState | Fault`
+struct ToArgs {
+    opcode: LitInt,
+    answers: Vec<Ident>,
+}
+
+impl Parse for ToArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let opcode = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        let key: Ident = input.parse()?;
+        if key != "answer" {
+            return Err(Error::new(key.span(), "expected `answer`"));
+        }
+        input.parse::<Token![=]>()?;
+
+        let answers = Punctuated::<Ident, Token![|]>::parse_separated_nonempty(input)?
+            .into_iter()
+            .collect();
+
+        Ok(Self { opcode, answers })
+    }
+}
+
+fn expand(item: ItemEnum) -> syn::Result<TokenStream2> {
+    let vis = &item.vis;
+
+    let mut shared_attrs = Vec::new();
+    let mut to_attrs = Vec::new();
+    let mut from_attrs = Vec::new();
+
+    for attr in &item.attrs {
+        if attr.path().is_ident(TO) {
+            to_attrs.extend(forwarded(attr)?);
+        } else if attr.path().is_ident(FROM) {
+            from_attrs.extend(forwarded(attr)?);
+        } else {
+            shared_attrs.push(attr);
+        }
+    }
+
+    let mut to_variants = Vec::new();
+    let mut from_variants = Vec::new();
+    let mut answered_by = Vec::new();
+    let mut messages = Vec::new();
+
+    let mut to_text = Text::default();
+    let mut from_text = Text::default();
+
+    let mut to_opcodes = Vec::new();
+    let mut from_opcodes = Vec::new();
+
+    for variant in &item.variants {
+        if let Some((_, discriminant)) = &variant.discriminant {
+            return Err(Error::new_spanned(
+                discriminant,
+                "opcodes are given in the direction annotation",
+            ));
+        }
+
+        let direction = direction(variant)?;
+
+        let opcodes = match direction {
+            Direction::To { .. } => &mut to_opcodes,
+            Direction::From { .. } => &mut from_opcodes,
+        };
+
+        let opcode = direction.opcode();
+        let value = opcode.base10_parse::<u8>()?;
+
+        if opcodes.contains(&value) {
+            return Err(Error::new(
+                opcode.span(),
+                format!("duplicate opcode {value:#04x}"),
+            ));
+        }
+
+        opcodes.push(value);
+
+        let attrs = variant
+            .attrs
+            .iter()
+            .filter(|attr| !attr.path().is_ident(TO) && !attr.path().is_ident(FROM));
+        let ident = &variant.ident;
+        let fields = &variant.fields;
+
+        let generated = quote! {
+            #(#attrs)*
+            #ident #fields = #opcode
+        };
+
+        let name = ident.to_string();
+        let payload = describe_fields(fields);
+        let (direction_path, answer_names) = match &direction {
+            Direction::To { answers, .. } => (
+                quote!(ToPeripheral),
+                answers.iter().map(Ident::to_string).collect(),
+            ),
+            Direction::From { .. } => (quote!(FromPeripheral), Vec::new()),
+        };
+
+        messages.push(quote! {
+            crate::command::description::Message {
+                name: #name,
+                direction: crate::command::description::Direction::#direction_path,
+                opcode: #opcode,
+                payload: &[#(#payload),*],
+                answers: &[#(#answer_names),*],
+            }
+        });
+
+        match &direction {
+            Direction::To { .. } => to_text.push(ident, fields),
+            Direction::From { .. } => from_text.push(ident, fields),
+        }
+
+        match &direction {
+            Direction::To { answers, .. } => {
+                to_variants.push(generated);
+                answered_by.push(quote! {
+                    Self::#ident { .. } => matches!(
+                        response,
+                        #(FromPeripheral::#answers { .. })|*
+                    )
+                });
+            }
+            Direction::From { .. } => from_variants.push(generated),
+        }
+    }
+
+    let to_text = to_text.expand(&quote!(ToPeripheral));
+    let from_text = from_text.expand(&quote!(FromPeripheral));
+
+    Ok(quote! {
+        #(#shared_attrs)*
+        #(#[#to_attrs])*
+        #[derive(::cookie_cutter::encoding::vanilla::SerializeIter)]
+        #[repr(u8)]
+        #vis enum ToPeripheral {
+            #(#to_variants,)*
+        }
+
+        #(#shared_attrs)*
+        #(#[#from_attrs])*
+        #[derive(::cookie_cutter::encoding::vanilla::SerializeIter)]
+        #[repr(u8)]
+        #vis enum FromPeripheral {
+            #(#from_variants,)*
+        }
+
+        /// Every message of the protocol.
+        #vis const MESSAGES: &[crate::command::description::Message] = &[
+            #(#messages,)*
+        ];
+
+        impl ToPeripheral {
+            /// Whether `response` is a valid answer to this command.
+            #vis fn answered_by(&self, response: &FromPeripheral) -> bool {
+                match self {
+                    #(#answered_by,)*
+                }
+            }
+        }
+
+        #to_text
+        #from_text
+    })
+}
+
+/// `Display` and `FromStr` arms of the variants of one direction.
+#[derive(Default)]
+struct Text {
+    display: Vec<TokenStream2>,
+    from_str: Vec<TokenStream2>,
+}
+
+impl Text {
+    fn push(&mut self, ident: &Ident, fields: &Fields) {
+        let name = ident.to_string();
+        let expected = format!("`{name}`");
+
+        match fields {
+            Fields::Unit => {
+                self.display.push(quote! {
+                    Self::#ident => f.write_str(#name)
+                });
+                self.from_str.push(quote! {
+                    #name => {
+                        arguments.none(#expected)?;
+
+                        Ok(Self::#ident)
+                    }
+                });
+            }
+            Fields::Unnamed(unnamed) => {
+                let bindings: Vec<_> = (0..unnamed.unnamed.len())
+                    .map(|i| Ident::new(&format!("field{i}"), Span::call_site()))
+                    .collect();
+                let format = format!("{name}({})", vec!["{}"; bindings.len()].join(", "));
+                let next = bindings.iter().map(|_| quote!(fields.next()?));
+
+                self.display.push(quote! {
+                    Self::#ident(#(#bindings),*) => write!(f, #format, #(#bindings),*)
+                });
+                self.from_str.push(quote! {
+                    #name => {
+                        let mut fields = arguments.tuple(#expected)?;
+                        let value = Self::#ident(#(#next),*);
+                        fields.end()?;
+
+                        Ok(value)
+                    }
+                });
+            }
+            Fields::Named(named) => {
+                let idents: Vec<_> = named
+                    .named
+                    .iter()
+                    .filter_map(|field| field.ident.as_ref())
+                    .collect();
+                let names = idents.iter().map(|ident| ident.to_string());
+                let format = format!(
+                    "{name} {{{{ {} }}}}",
+                    idents
+                        .iter()
+                        .map(|ident| format!("{ident}: {{}}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+
+                self.display.push(quote! {
+                    Self::#ident { #(#idents),* } => write!(f, #format, #(#idents),*)
+                });
+                self.from_str.push(quote! {
+                    #name => {
+                        let fields = arguments.named(#expected)?;
+
+                        Ok(Self::#ident { #(#idents: fields.get(#names)?),* })
+                    }
+                });
+            }
+        }
+    }
+
+    fn expand(self, ty: &TokenStream2) -> TokenStream2 {
+        let Self { display, from_str } = self;
+        let expected = ty.to_string();
+
+        quote! {
+            #[cfg(feature = "std")]
+            impl ::core::fmt::Display for #ty {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self {
+                        #(#display,)*
+                    }
+                }
+            }
+
+            #[cfg(feature = "std")]
+            impl ::core::str::FromStr for #ty {
+                type Err = crate::text::ParseError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    let (name, arguments) = crate::text::split_variant(s)?;
+
+                    match name {
+                        #(#from_str)*
+                        _ => Err(crate::text::ParseError::new(#expected)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `Field` descriptions of the payload of a variant.
+fn describe_fields(fields: &Fields) -> Vec<TokenStream2> {
+    fields
+        .iter()
+        .map(|field| {
+            let name = match &field.ident {
+                Some(ident) => {
+                    let ident = ident.to_string();
+                    quote!(Some(#ident))
+                }
+                None => quote!(None),
+            };
+
+            let ty = field.ty.to_token_stream().to_string();
+
+            quote! {
+                crate::command::description::Field {
+                    name: #name,
+                    ty: #ty,
+                }
+            }
+        })
+        .collect()
+}
+
+/// The attributes listed in `#[to_peripheral(...)]` on the enum.
+fn forwarded(attr: &Attribute) -> syn::Result<Punctuated<Meta, Token![,]>> {
+    attr.parse_args_with(Punctuated::parse_terminated)
+}
+
+fn direction(variant: &Variant) -> syn::Result<Direction> {
+    let mut found = None;
+
+    for attr in &variant.attrs {
+        let direction = if attr.path().is_ident(TO) {
+            let ToArgs { opcode, answers } = attr.parse_args()?;
+
+            Direction::To { opcode, answers }
+        } else if attr.path().is_ident(FROM) {
+            Direction::From {
+                opcode: attr.parse_args()?,
+            }
+        } else {
+            continue;
+        };
+
+        if found.is_some() {
+            return Err(Error::new_spanned(
+                attr,
+                "a variant travels in exactly one direction",
+            ));
+        }
+
+        found = Some(direction);
+    }
+
+    found.ok_or_else(|| {
+        Error::new_spanned(
+            &variant.ident,
+            format!("expected `#[{TO}(...)]` or `#[{FROM}(...)]`"),
+        )
+    })
+}
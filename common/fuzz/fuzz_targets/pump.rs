@@ -0,0 +1,17 @@
+//! Arbitrary bytes through everything which parses pump traffic.
+//!
+//! ```text
+//! cargo +nightly fuzz run pump
+//! ```
+
+#![no_main]
+
+#[path = "shared.rs"]
+mod shared;
+
+use common::command::pump::{FromPeripheral, Request, Response, ToPeripheral};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    shared::traffic::<ToPeripheral, FromPeripheral, Request, Response>(data);
+});
@@ -0,0 +1,51 @@
+//! What every fuzz target does, for the protocol it is given.
+
+use common::frame;
+use cookie_cutter::SerializeIter;
+use embedded_command::command_buffer::CommandBuffer;
+
+/// Feed `data` to everything which parses traffic of one protocol.
+pub fn traffic<To, From, Request, Response>(data: &[u8])
+where
+    To: SerializeIter,
+    From: SerializeIter,
+    Request: SerializeIter,
+    Response: SerializeIter,
+{
+    // bare messages
+    let _ = To::deserialize_iter(data);
+    let _ = From::deserialize_iter(data);
+
+    // framed traffic in both directions
+    drain::<Request>(data);
+    drain::<Response>(data);
+}
+
+/// Ingest `data` in chunks (sized by its first byte) and
+/// decode every frame, like main and the dummy do.
+fn drain<T: SerializeIter>(data: &[u8]) {
+    let Some((&chunk_len, data)) = data.split_first() else {
+        return;
+    };
+
+    let mut buf = CommandBuffer::<256>::new();
+
+    for chunk in data.chunks(chunk_len.max(1) as usize) {
+        // main gives up on overflow too
+        if buf.ingest(chunk.iter()).is_err() {
+            return;
+        }
+
+        loop {
+            let mut iter = buf.iter();
+
+            // incomplete frames stay buffered
+            if let Err(frame::Error::EndOfInput) = frame::decode::<T>(&mut iter) {
+                break;
+            }
+
+            let memento = iter.capture();
+            buf.flush(memento);
+        }
+    }
+}
@@ -1,2 +1,50 @@
+use cookie_cutter::encoding::vanilla;
+
+#[cfg(feature = "std")]
+use crate::text;
+
+pub mod description;
 pub mod pump;
 pub mod temperature;
+
+/// Pairs a response with the request it answers.
+///
+/// The peripheral echoes the ID of every request
+/// in its response so late replies to earlier
+/// requests can be told apart.
+pub type TransactionId = u8;
+
+/// Selects one peripheral among several sharing a bus.
+///
+/// Peripherals only answer requests carrying their
+/// own address, and tag responses with it.
+pub type Address = u8;
+
+/// Answered by any peripheral, whatever its address.
+///
+/// Only meaningful when the peripheral is alone on its bus.
+pub const POINT_TO_POINT: Address = 0x00;
+
+/// Why a peripheral refused a request.
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum NackReason {
+    /// The opcode is not part of the protocol the peripheral speaks.
+    UnknownOpcode = 0x0c,
+    /// The opcode is known but its payload is malformed.
+    BadPayload = 0xba,
+    /// The peripheral cannot handle the request right now.
+    Busy = 0xb5,
+    /// The peripheral is still starting up.
+    NotReady = 0x2e,
+}
+
+#[cfg(feature = "std")]
+text::named!(NackReason {
+    UnknownOpcode => "unknown-opcode",
+    BadPayload => "bad-payload",
+    Busy => "busy",
+    NotReady => "not-ready",
+});
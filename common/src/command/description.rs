@@ -0,0 +1,36 @@
+//! Descriptions of the messages of a protocol, generated by
+//! `#[bidirectional]` for anything which needs to know about
+//! the protocol without speaking it, like documentation or
+//! conformance checks of peripheral firmware.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Direction {
+    ToPeripheral,
+    FromPeripheral,
+}
+
+/// A field of a message payload.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Field {
+    /// [`None`] for tuple variants.
+    pub name: Option<&'static str>,
+    /// The Rust type of the field, as written in the protocol.
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub ty: &'static str,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Message {
+    pub name: &'static str,
+    pub direction: Direction,
+    pub opcode: u8,
+    pub payload: &'static [Field],
+    /// Messages which may answer this one.
+    ///
+    /// Empty for messages from the peripheral.
+    pub answers: &'static [&'static str],
+}
@@ -1,32 +1,191 @@
+use common_macros::bidirectional;
 use cookie_cutter::encoding::vanilla;
 
-use crate::types::pump::PumpState;
+use super::{Address, NackReason, TransactionId};
+#[cfg(feature = "std")]
+use crate::text;
+use crate::types::{
+    device::Identity,
+    link::BaudRate,
+    pump::{Failsafe, PumpState, Speed, Telemetry},
+};
 
-// NOTE: this pattern is used a lot,
-// maybe i should make another higher level
-// proc macro which combines bidirectional
-// commands and separates them based on
-// annotations.
+/// The revision of the pump protocol described here.
+///
+/// 2: variable speed.
+/// 3: telemetry.
+/// 4: latched faults.
+/// 5: addressing.
+/// 6: heartbeat failsafe.
+/// 7: negative acknowledgements.
+/// 8: baud rate negotiation.
+pub const PROTOCOL_VERSION: u8 = 8;
 
-#[derive(Clone, Copy, vanilla::SerializeIter)]
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[repr(u8)]
-pub enum ToPeripheral {
-    Set(PumpState) = 0xca,
-    Get = 0x11,
-}
+#[bidirectional]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[to_peripheral(
+    derive(Clone, Copy),
+    cfg_attr(feature = "defmt", derive(defmt::Format))
+)]
+pub enum Protocol {
+    #[to_peripheral(0xca, answer = PumpState | Fault | Nack)]
+    Set(PumpState),
+    #[to_peripheral(0x11, answer = PumpState | Fault | Nack)]
+    Get,
+    /// Set the speed the pump runs at while on.
+    ///
+    /// Answered with the speed the pump settled on.
+    #[to_peripheral(0x5d, answer = Speed | Fault | Nack)]
+    SetSpeed(Speed),
+    /// Request current draw, speed and flow.
+    #[to_peripheral(0x7a, answer = Telemetry | Fault | Nack)]
+    GetTelemetry,
+    /// Ask the peripheral what it is.
+    #[to_peripheral(0x1d, answer = Identity | Nack)]
+    Identify,
+    /// Unlatch the active fault.
+    ///
+    /// Answered with the pump state if the fault
+    /// cleared, or the fault if its cause persists.
+    #[to_peripheral(0xcf, answer = PumpState | Fault | Nack)]
+    ClearFault,
+    /// Arm (or disarm) the failsafe.
+    ///
+    /// The failsafe is disarmed until configured.
+    /// Answered with the failsafe now in effect.
+    #[to_peripheral(0xf5, answer = Failsafe | Nack)]
+    ConfigureFailsafe(Failsafe),
+    /// Keep the failsafe from tripping while
+    /// there is nothing else to send, see [`Failsafe`].
+    ///
+    /// Answered with the pump state.
+    #[to_peripheral(0x4b, answer = PumpState | Fault | Nack)]
+    Heartbeat,
+    /// Switch the link to another line rate.
+    ///
+    /// Answered at the old rate, see [`BaudRate`].
+    #[to_peripheral(0xb4, answer = Baud | Nack)]
+    SetBaud(BaudRate),
 
-#[derive(vanilla::SerializeIter)]
-#[repr(u8)]
-pub enum FromPeripheral {
-    PumpState(PumpState) = 0xaa,
-    Fault(Fault) = 0x1f,
+    #[from_peripheral(0xaa)]
+    PumpState(PumpState),
+    #[from_peripheral(0x1f)]
+    Fault(Fault),
+    #[from_peripheral(0xd1)]
+    Identity(Identity),
+    #[from_peripheral(0xd5)]
+    Speed(Speed),
+    #[from_peripheral(0xa7)]
+    Telemetry(Telemetry),
+    #[from_peripheral(0xf7)]
+    Failsafe(Failsafe),
+    /// The request was refused.
+    #[from_peripheral(0x15)]
+    Nack { reason: NackReason },
+    /// The rate the pump switches to.
+    #[from_peripheral(0xb6)]
+    Baud(BaudRate),
 }
 
-#[derive(vanilla::SerializeIter)]
+/// Faults are latched.
+///
+/// When a fault trips the pump turns off and answers
+/// every request other than `Identify`, `ClearFault`,
+/// `ConfigureFailsafe` and `SetBaud` with the fault, until
+/// it is cleared with `ClearFault`. Heartbeats answered with
+/// the fault still keep the failsafe from tripping.
+///
+/// A latched fault overrides the failsafe, so a controller which
+/// gives up on clearing it leaves the pump off.
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum Fault {
     Temperature = 0xde,
     Current = 0xad,
+    /// The motor is not turning while driven.
+    Stall = 0x57,
+    /// The pump is running without liquid.
+    DryRun = 0xd7,
+    OverVoltage = 0x0e,
+    /// Anything else going wrong inside the pump.
+    Internal = 0xee,
+}
+
+/// A command to the addressed peripheral
+/// tagged with a transaction ID.
+#[derive(Debug, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Request {
+    pub address: Address,
+    pub id: TransactionId,
+    pub command: ToPeripheral,
+}
+
+/// A response from the peripheral at `address` echoing
+/// the transaction ID of the request it answers.
+#[derive(Debug, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Response {
+    pub address: Address,
+    pub id: TransactionId,
+    pub command: FromPeripheral,
+}
+
+#[cfg(feature = "std")]
+text::named!(Fault {
+    Temperature => "temperature",
+    Current => "current",
+    Stall => "stall",
+    DryRun => "dry-run",
+    OverVoltage => "over-voltage",
+    Internal => "internal",
+});
+
+/// `@32 #3 Get`
+#[cfg(feature = "std")]
+impl core::fmt::Display for Request {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "@{} #{} {}", self.address, self.id, self.command)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Request {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, id, command) = text::split_envelope(s, "a request like `@32 #3 Get`")?;
+
+        Ok(Self {
+            address,
+            id,
+            command: command.parse()?,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for Response {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "@{} #{} {}", self.address, self.id, self.command)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Response {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, id, command) =
+            text::split_envelope(s, "a response like `@32 #3 PumpState(on)`")?;
+
+        Ok(Self {
+            address,
+            id,
+            command: command.parse()?,
+        })
+    }
 }
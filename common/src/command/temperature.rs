@@ -1,17 +1,172 @@
+use common_macros::bidirectional;
 use cookie_cutter::encoding::vanilla;
 
-use crate::types::temperature::Temperature;
+use super::{Address, NackReason, TransactionId};
+#[cfg(feature = "std")]
+use crate::text;
+use crate::types::{
+    device::Identity,
+    link::BaudRate,
+    temperature::{Calibration, Temperature},
+};
 
-#[derive(vanilla::SerializeIter)]
-#[repr(u8)]
-pub enum ToPeripheral {
+/// The revision of the temperature protocol described here.
+///
+/// 2: temperatures are hundredths of a degree in an `i16`.
+/// 3: sensor faults.
+/// 4: addressing.
+/// 5: streaming.
+/// 6: negative acknowledgements.
+/// 7: baud rate negotiation.
+/// 8: calibration.
+pub const PROTOCOL_VERSION: u8 = 8;
+
+#[bidirectional]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[to_peripheral(
+    derive(Clone, Copy),
+    cfg_attr(feature = "defmt", derive(defmt::Format))
+)]
+pub enum Protocol {
     /// Request a new measurement.
-    Read = 0xbe,
+    #[to_peripheral(0xbe, answer = Temperature | Fault | Nack)]
+    Read,
+    /// Ask the peripheral what it is.
+    #[to_peripheral(0x1d, answer = Identity | Nack)]
+    Identify,
+    /// Have the peripheral push a measurement every `period_ms`.
+    ///
+    /// Answered with the period the peripheral settled on,
+    /// then every measurement (or fault) is pushed echoing
+    /// the transaction ID of this request.
+    #[to_peripheral(0x5b, answer = Subscribed | Nack)]
+    Subscribe { period_ms: u16 },
+    /// Stop pushing measurements.
+    #[to_peripheral(0x0b, answer = Unsubscribed | Nack)]
+    Unsubscribe,
+    /// Switch the bus to another line rate.
+    ///
+    /// Answered at the old rate, see [`BaudRate`].
+    #[to_peripheral(0xb4, answer = Baud | Nack)]
+    SetBaud(BaudRate),
+    /// Correct subsequent readings, see [`Calibration`].
+    #[to_peripheral(0xc5, answer = Calibration | Nack)]
+    SetCalibration(Calibration),
+    #[to_peripheral(0xc6, answer = Calibration | Nack)]
+    GetCalibration,
+
+    /// A temperature measurement.
+    #[from_peripheral(0xef)]
+    Temperature(Temperature),
+    /// The peripheral's identity.
+    #[from_peripheral(0xd1)]
+    Identity(Identity),
+    /// The sensor could not produce a valid measurement.
+    #[from_peripheral(0x1f)]
+    Fault(Fault),
+    /// Measurements will be pushed every `period_ms`.
+    #[from_peripheral(0xb5)]
+    Subscribed { period_ms: u16 },
+    /// Measurements are no longer pushed.
+    #[from_peripheral(0xb0)]
+    Unsubscribed,
+    /// The request was refused.
+    #[from_peripheral(0x15)]
+    Nack { reason: NackReason },
+    /// The rate the sensor switches to.
+    #[from_peripheral(0xb6)]
+    Baud(BaudRate),
+    /// The calibration in effect.
+    #[from_peripheral(0xca)]
+    Calibration(Calibration),
 }
 
-#[derive(vanilla::SerializeIter)]
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
-pub enum FromPeripheral {
-    /// A temperature value in Celsius.
-    Temperature(Temperature) = 0xef,
+pub enum Fault {
+    /// The probe is disconnected or broken.
+    OpenCircuit = 0x0c,
+    /// The probe leads are shorted.
+    ShortCircuit = 0x5c,
+    /// The measurement is outside the range of the sensor.
+    OutOfRange = 0x0e,
+    /// The sensor has not finished its first conversion.
+    NotReady = 0x2e,
+}
+
+/// A command to the addressed peripheral
+/// tagged with a transaction ID.
+#[derive(Debug, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Request {
+    pub address: Address,
+    pub id: TransactionId,
+    pub command: ToPeripheral,
+}
+
+/// A response from the peripheral at `address` echoing
+/// the transaction ID of the request it answers.
+#[derive(Debug, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Response {
+    pub address: Address,
+    pub id: TransactionId,
+    pub command: FromPeripheral,
+}
+
+#[cfg(feature = "std")]
+text::named!(Fault {
+    OpenCircuit => "open-circuit",
+    ShortCircuit => "short-circuit",
+    OutOfRange => "out-of-range",
+    NotReady => "not-ready",
+});
+
+/// `@16 #3 Read`
+#[cfg(feature = "std")]
+impl core::fmt::Display for Request {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "@{} #{} {}", self.address, self.id, self.command)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Request {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, id, command) = text::split_envelope(s, "a request like `@16 #3 Read`")?;
+
+        Ok(Self {
+            address,
+            id,
+            command: command.parse()?,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for Response {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "@{} #{} {}", self.address, self.id, self.command)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Response {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, id, command) =
+            text::split_envelope(s, "a response like `@16 #3 Unsubscribed`")?;
+
+        Ok(Self {
+            address,
+            id,
+            command: command.parse()?,
+        })
+    }
 }
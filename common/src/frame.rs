@@ -0,0 +1,236 @@
+//! Framing used for every message on the peripheral links.
+//!
+//! A message is first wrapped in an envelope:
+//!
+//! | length | payload (`length` bytes) | CRC-16 (little endian) |
+//!
+//! The CRC (CRC-16/CCITT-FALSE) covers the length byte and the payload,
+//! so a flipped bit anywhere in the envelope is caught before the payload
+//! is handed to the deserializer.
+//!
+//! The envelope is then COBS encoded and surrounded by `0x00` delimiters:
+//!
+//! | 0x00 | COBS(envelope) | 0x00 |
+//!
+//! COBS guarantees `0x00` never appears inside a frame, so a receiver
+//! which lost track of the stream (line glitch, garbage, dropped bytes)
+//! only ever loses the frame it is in, and picks up again at the next
+//! delimiter.
+
+use cookie_cutter::{error::EndOfInput, SerializeIter};
+
+/// The largest payload a single frame can carry.
+pub const MAX_PAYLOAD: usize = 32;
+
+/// The size of the largest envelope (length + payload + CRC).
+const MAX_ENVELOPE: usize = MAX_PAYLOAD + 3;
+
+/// The size of the largest COBS encoded envelope.
+///
+/// The envelope is shorter than 254 bytes so COBS adds exactly one byte.
+const MAX_ENCODED: usize = MAX_ENVELOPE + 1;
+
+/// The largest number of bytes a single frame occupies on the wire.
+pub const MAX_FRAME: usize = MAX_ENCODED + 2;
+
+/// Separates frames on the wire.
+pub const DELIMITER: u8 = 0x00;
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The closing delimiter of the frame has not arrived yet.
+    EndOfInput,
+    /// The frame failed its COBS, length or CRC check.
+    Corrupt,
+    /// The frame is intact but does not contain a valid message.
+    Deserialize(cookie_cutter::error::Error),
+}
+
+/// CRC-16/CCITT-FALSE (poly `0x1021`, init `0xffff`).
+pub const fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xffff_u16;
+    let mut i = 0;
+
+    while i < data.len() {
+        crc ^= (data[i] as u16) << 8;
+
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+
+            bit += 1;
+        }
+
+        i += 1;
+    }
+
+    crc
+}
+
+/// COBS encode `src` into `dest`, returning the number of bytes written.
+///
+/// `dest` must be at least one byte longer than `src`.
+fn cobs_encode(src: &[u8], dest: &mut [u8]) -> usize {
+    let mut code_index = 0;
+    let mut code = 1;
+    let mut n = 1;
+
+    for byte in src {
+        if *byte == 0 {
+            dest[code_index] = code;
+            code_index = n;
+            code = 1;
+            n += 1;
+        } else {
+            dest[n] = *byte;
+            code += 1;
+            n += 1;
+
+            if code == 0xff {
+                dest[code_index] = code;
+                code_index = n;
+                code = 1;
+                n += 1;
+            }
+        }
+    }
+
+    dest[code_index] = code;
+
+    n
+}
+
+/// COBS decode `src` into `dest`, returning the number of bytes written.
+///
+/// Returns [`None`] if `src` is not valid COBS or does not fit in `dest`.
+fn cobs_decode(src: &[u8], dest: &mut [u8]) -> Option<usize> {
+    let mut i = 0;
+    let mut n = 0;
+
+    while i < src.len() {
+        let code = src[i] as usize;
+        i += 1;
+
+        if code == 0 || i + code - 1 > src.len() {
+            return None;
+        }
+
+        for _ in 1..code {
+            *dest.get_mut(n)? = src[i];
+            i += 1;
+            n += 1;
+        }
+
+        if code != 0xff && i < src.len() {
+            *dest.get_mut(n)? = 0;
+            n += 1;
+        }
+    }
+
+    Some(n)
+}
+
+/// Serialize `message` into `dest` as a complete frame.
+///
+/// Returns the number of bytes of `dest` which make up the frame.
+pub fn encode<T: SerializeIter>(
+    message: &T,
+    dest: &mut [u8; MAX_FRAME],
+) -> Result<usize, EndOfInput> {
+    let mut envelope = [0; MAX_ENVELOPE];
+
+    let mut len = 0;
+    message.serialize_iter(envelope[1..1 + MAX_PAYLOAD].iter_mut().inspect(|_| {
+        len += 1;
+    }))?;
+
+    envelope[0] = len as u8;
+
+    let crc = crc16(&envelope[..1 + len]);
+    envelope[1 + len..3 + len].copy_from_slice(&crc.to_le_bytes());
+
+    dest[0] = DELIMITER;
+    let n = cobs_encode(&envelope[..3 + len], &mut dest[1..]);
+    dest[1 + n] = DELIMITER;
+
+    Ok(n + 2)
+}
+
+/// Deserialize one frame from `src`.
+///
+/// Bytes are consumed from `src` up to and including the delimiter
+/// closing the frame, regardless of whether the frame was valid, so
+/// the next call starts on a frame boundary. Empty frames (back to
+/// back delimiters) are skipped.
+///
+/// The exception is a run of bytes longer than any frame without a
+/// delimiter. [`Error::Corrupt`] is returned as soon as that is clear,
+/// without waiting for the delimiter, so receive buffers never have to
+/// hold more than a frame. What is left of the run is dropped by the
+/// following calls, each returning [`Error::Corrupt`], and the call
+/// after that starts on a frame boundary again.
+pub fn decode<'a, T: SerializeIter>(src: impl IntoIterator<Item = &'a u8>) -> Result<T, Error> {
+    let mut payload = [0; MAX_PAYLOAD];
+    let len = decode_payload(src, &mut payload)?;
+
+    T::deserialize_iter(&payload[..len]).map_err(Error::Deserialize)
+}
+
+/// Copy the payload of one frame from `src` into `dest`
+/// without deserializing it, returning its length.
+///
+/// Consumes `src` like [`decode`], and never returns [`Error::Deserialize`].
+pub fn decode_payload<'a>(
+    src: impl IntoIterator<Item = &'a u8>,
+    dest: &mut [u8; MAX_PAYLOAD],
+) -> Result<usize, Error> {
+    let mut src = src.into_iter();
+
+    let mut encoded = [0; MAX_ENCODED];
+    let mut len = 0;
+
+    loop {
+        let byte = *src.next().ok_or(Error::EndOfInput)?;
+
+        if byte == DELIMITER {
+            if len == 0 {
+                continue;
+            }
+
+            break;
+        }
+
+        // too long to be a frame, see `decode` for
+        // why this does not wait for the delimiter
+        if len == encoded.len() {
+            return Err(Error::Corrupt);
+        }
+
+        encoded[len] = byte;
+        len += 1;
+    }
+
+    let mut envelope = [0; MAX_ENVELOPE];
+    let n = cobs_decode(&encoded[..len], &mut envelope).ok_or(Error::Corrupt)?;
+
+    let payload_len = envelope[0] as usize;
+
+    if n < 3 || payload_len != n - 3 {
+        return Err(Error::Corrupt);
+    }
+
+    let (body, crc) = envelope[..n].split_at(payload_len + 1);
+
+    if crc16(body) != u16::from_le_bytes([crc[0], crc[1]]) {
+        return Err(Error::Corrupt);
+    }
+
+    let payload = &body[1..];
+    dest[..payload.len()].copy_from_slice(payload);
+
+    Ok(payload.len())
+}
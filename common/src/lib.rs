@@ -1,4 +1,7 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 pub mod command;
+pub mod frame;
+#[cfg(feature = "std")]
+pub mod text;
 pub mod types;
@@ -0,0 +1,217 @@
+//! Text representations of types and commands for host tooling.
+//!
+//! Every type prints in a form its `FromStr` implementation accepts.
+//! Commands print like Rust expressions with their payloads in text
+//! form, e.g. `Set(on)` or `Subscribe { period_ms: 1000 }`.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::command::{Address, TransactionId};
+
+/// Text which does not describe a value of the expected type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParseError {
+    expected: &'static str,
+}
+
+impl ParseError {
+    pub(crate) const fn new(expected: &'static str) -> Self {
+        Self { expected }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}", self.expected)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Implements `Display` and `FromStr` for a fieldless enum.
+macro_rules! named {
+    ($ty:ty { $($variant:ident => $name:literal),* $(,)? }) => {
+        impl core::fmt::Display for $ty {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(match self {
+                    $(Self::$variant => $name,)*
+                })
+            }
+        }
+
+        impl core::str::FromStr for $ty {
+            type Err = crate::text::ParseError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s.trim() {
+                    $($name => Ok(Self::$variant),)*
+                    _ => Err(crate::text::ParseError::new(stringify!($ty))),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use named;
+
+/// Parses `s` into `T`, reporting `expected` on failure.
+pub(crate) fn parse<T: FromStr>(s: &str, expected: &'static str) -> Result<T, ParseError> {
+    s.trim().parse().map_err(|_| ParseError::new(expected))
+}
+
+/// Parses a decimal with up to `decimals` places into an integer
+/// scaled by `10^decimals`, e.g. `-1.5` with two places into `-150`.
+pub(crate) fn parse_fixed(
+    s: &str,
+    decimals: u32,
+    expected: &'static str,
+) -> Result<i32, ParseError> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+
+    // parse rejects signs of their own, but not a leading `+`
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !digits(whole) || !digits(fraction) || fraction.len() > decimals as usize
+    {
+        return Err(ParseError::new(expected));
+    }
+
+    let whole: i32 = parse(whole, expected)?;
+    let fraction: i32 = if fraction.is_empty() {
+        0
+    } else {
+        // pad to the full number of places
+        parse::<i32>(fraction, expected)? * 10_i32.pow(decimals - fraction.len() as u32)
+    };
+
+    let magnitude = whole
+        .checked_mul(10_i32.pow(decimals))
+        .and_then(|scaled| scaled.checked_add(fraction))
+        .ok_or(ParseError::new(expected))?;
+
+    Ok(if negative { -magnitude } else { magnitude })
+}
+
+/// The arguments following the name of a variant.
+pub(crate) enum Arguments<'a> {
+    /// `Name`
+    None,
+    /// `Name(a, b)`
+    Tuple(&'a str),
+    /// `Name { x: a, y: b }`
+    Named(&'a str),
+}
+
+/// Splits a variant into its name and arguments.
+pub(crate) fn split_variant(s: &str) -> Result<(&str, Arguments<'_>), ParseError> {
+    const EXPECTED: &str = "a message";
+
+    let s = s.trim();
+
+    let Some(start) = s.find(['(', '{']) else {
+        return Ok((s, Arguments::None));
+    };
+
+    let (name, rest) = s.split_at(start);
+
+    let arguments = if let Some(inner) = rest.strip_prefix('(') {
+        Arguments::Tuple(inner.strip_suffix(')').ok_or(ParseError::new(EXPECTED))?)
+    } else {
+        let inner = rest.strip_prefix('{').and_then(|s| s.strip_suffix('}'));
+
+        Arguments::Named(inner.ok_or(ParseError::new(EXPECTED))?)
+    };
+
+    Ok((name.trim(), arguments))
+}
+
+impl<'a> Arguments<'a> {
+    pub(crate) fn none(self, expected: &'static str) -> Result<(), ParseError> {
+        match self {
+            Self::None => Ok(()),
+            _ => Err(ParseError::new(expected)),
+        }
+    }
+
+    pub(crate) fn tuple(self, expected: &'static str) -> Result<TupleFields<'a>, ParseError> {
+        match self {
+            Self::Tuple(inner) => Ok(TupleFields {
+                fields: inner.split(','),
+                expected,
+            }),
+            _ => Err(ParseError::new(expected)),
+        }
+    }
+
+    pub(crate) fn named(self, expected: &'static str) -> Result<NamedFields<'a>, ParseError> {
+        match self {
+            Self::Named(inner) => Ok(NamedFields { inner, expected }),
+            _ => Err(ParseError::new(expected)),
+        }
+    }
+}
+
+pub(crate) struct TupleFields<'a> {
+    fields: core::str::Split<'a, char>,
+    expected: &'static str,
+}
+
+impl TupleFields<'_> {
+    pub(crate) fn next<T: FromStr>(&mut self) -> Result<T, ParseError> {
+        parse(
+            self.fields.next().ok_or(ParseError::new(self.expected))?,
+            self.expected,
+        )
+    }
+
+    /// Fails if there are fields left.
+    pub(crate) fn end(mut self) -> Result<(), ParseError> {
+        match self.fields.next() {
+            None => Ok(()),
+            Some(_) => Err(ParseError::new(self.expected)),
+        }
+    }
+}
+
+pub(crate) struct NamedFields<'a> {
+    inner: &'a str,
+    expected: &'static str,
+}
+
+impl NamedFields<'_> {
+    pub(crate) fn get<T: FromStr>(&self, name: &str) -> Result<T, ParseError> {
+        let value = self
+            .inner
+            .split(',')
+            .filter_map(|field| field.split_once(':'))
+            .find(|(field, _)| field.trim() == name)
+            .map(|(_, value)| value)
+            .ok_or(ParseError::new(self.expected))?;
+
+        parse(value, self.expected)
+    }
+}
+
+/// Splits `@address #id message` into its parts.
+pub(crate) fn split_envelope<'a>(
+    s: &'a str,
+    expected: &'static str,
+) -> Result<(Address, TransactionId, &'a str), ParseError> {
+    let mut parts = s.trim().splitn(3, ' ');
+    let mut part = |prefix| {
+        parts
+            .next()
+            .and_then(|part| part.strip_prefix(prefix))
+            .ok_or(ParseError::new(expected))
+    };
+
+    let address = parse(part('@')?, expected)?;
+    let id = parse(part('#')?, expected)?;
+    let message = parts.next().ok_or(ParseError::new(expected))?;
+
+    Ok((address, id, message))
+}
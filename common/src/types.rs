@@ -1,2 +1,4 @@
+pub mod device;
+pub mod link;
 pub mod pump;
 pub mod temperature;
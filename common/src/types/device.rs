@@ -0,0 +1,100 @@
+use cookie_cutter::encoding::vanilla;
+
+#[cfg(feature = "std")]
+use crate::text;
+
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Kind {
+    Temperature = 0x7e,
+    Pump = 0x9a,
+}
+
+/// A `major.minor.patch` version number.
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+/// What a peripheral reports about itself.
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Identity {
+    pub kind: Kind,
+    /// The revision of the protocol the peripheral speaks.
+    pub protocol: u8,
+    pub firmware: Version,
+}
+
+#[cfg(feature = "std")]
+text::named!(Kind {
+    Temperature => "temperature",
+    Pump => "pump",
+});
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for Version {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Version {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "a version like `1.2.3`";
+
+        let mut parts = s.trim().split('.');
+        let mut part = || text::parse(parts.next().unwrap_or_default(), EXPECTED);
+
+        let version = Self {
+            major: part()?,
+            minor: part()?,
+            patch: part()?,
+        };
+
+        match parts.next() {
+            None => Ok(version),
+            Some(_) => Err(text::ParseError::new(EXPECTED)),
+        }
+    }
+}
+
+/// `pump protocol 7 firmware 0.1.0`
+#[cfg(feature = "std")]
+impl core::fmt::Display for Identity {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} protocol {} firmware {}",
+            self.kind, self.protocol, self.firmware
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Identity {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "an identity like `pump protocol 7 firmware 0.1.0`";
+
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            [kind, "protocol", protocol, "firmware", firmware] => Ok(Self {
+                kind: text::parse(kind, EXPECTED)?,
+                protocol: text::parse(protocol, EXPECTED)?,
+                firmware: text::parse(firmware, EXPECTED)?,
+            }),
+            _ => Err(text::ParseError::new(EXPECTED)),
+        }
+    }
+}
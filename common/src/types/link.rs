@@ -0,0 +1,50 @@
+use cookie_cutter::encoding::vanilla;
+
+#[cfg(feature = "std")]
+use crate::text;
+
+/// How long a peripheral which just switched rates waits
+/// for a valid request before falling back to [`BaudRate::DEFAULT`].
+pub const CONFIRM_TIMEOUT_MS: u16 = 500;
+
+/// Line rate of a peripheral link.
+///
+/// Links come up at [`BaudRate::DEFAULT`]. The controller proposes
+/// another rate with `SetBaud`, which the peripheral answers at the
+/// old rate before switching. The first request received at the new
+/// rate confirms it, a peripheral which receives none within
+/// [`CONFIRM_TIMEOUT_MS`] switches back to the default.
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum BaudRate {
+    Bps9600 = 0x96,
+    Bps19200 = 0x19,
+    Bps38400 = 0x38,
+    Bps57600 = 0x57,
+    Bps115200 = 0x11,
+}
+
+impl BaudRate {
+    pub const DEFAULT: Self = Self::Bps9600;
+
+    pub const fn as_bps(self) -> u32 {
+        match self {
+            Self::Bps9600 => 9600,
+            Self::Bps19200 => 19200,
+            Self::Bps38400 => 38400,
+            Self::Bps57600 => 57600,
+            Self::Bps115200 => 115200,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+text::named!(BaudRate {
+    Bps9600 => "9600",
+    Bps19200 => "19200",
+    Bps38400 => "38400",
+    Bps57600 => "57600",
+    Bps115200 => "115200",
+});
@@ -1,9 +1,230 @@
 use cookie_cutter::encoding::vanilla;
 
-#[derive(Clone, Copy, PartialEq, vanilla::SerializeIter)]
+#[cfg(feature = "std")]
+use crate::text;
+
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum PumpState {
     On = 0x5e,
     Off = 0xed,
 }
+
+/// Pump speed as a duty cycle in percent.
+///
+/// On the wire any byte decodes, peripherals answer
+/// speeds over 100% with [`NackReason::BadPayload`].
+///
+/// [`NackReason::BadPayload`]: crate::command::NackReason::BadPayload
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
+pub struct Speed(u8);
+
+impl Speed {
+    pub const STOPPED: Self = Self(0);
+    pub const FULL: Self = Self(100);
+
+    /// Saturates at [`Speed::FULL`].
+    pub const fn from_percent(percent: u8) -> Self {
+        if percent > Self::FULL.0 {
+            Self::FULL
+        } else {
+            Self(percent)
+        }
+    }
+
+    /// Returns [`None`] above [`Speed::FULL`].
+    pub const fn try_from_percent(percent: u8) -> Option<Self> {
+        if percent > Self::FULL.0 {
+            None
+        } else {
+            Some(Self(percent))
+        }
+    }
+
+    pub const fn as_percent(self) -> u8 {
+        self.0
+    }
+
+    /// Whether the speed is one a pump can run at, anything
+    /// not made by [`Speed::from_percent`] may not be.
+    pub const fn is_valid(self) -> bool {
+        self.0 <= Self::FULL.0
+    }
+}
+
+/// A speed over 100%.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidSpeed(pub u8);
+
+impl core::fmt::Display for InvalidSpeed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "speed of {}% is over 100%", self.0)
+    }
+}
+
+impl TryFrom<u8> for Speed {
+    type Error = InvalidSpeed;
+
+    fn try_from(percent: u8) -> Result<Self, Self::Error> {
+        Self::try_from_percent(percent).ok_or(InvalidSpeed(percent))
+    }
+}
+
+impl From<Speed> for u8 {
+    fn from(speed: Speed) -> Self {
+        speed.0
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Speed {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "{}%", self.0)
+    }
+}
+
+impl core::fmt::Debug for Speed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for Speed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+/// `50%` or `50`, out of range speeds are refused rather than saturated.
+#[cfg(feature = "std")]
+impl core::str::FromStr for Speed {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "a speed from 0% to 100%";
+
+        let s = s.trim();
+        let percent: u8 = text::parse(s.strip_suffix('%').unwrap_or(s), EXPECTED)?;
+
+        Self::try_from_percent(percent).ok_or(text::ParseError::new(EXPECTED))
+    }
+}
+
+/// Live readings from the pump.
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Telemetry {
+    /// Motor current draw in milliamps.
+    pub current_ma: u16,
+    /// Shaft speed in revolutions per minute.
+    pub rpm: u16,
+    /// Flow rate in millilitres per minute.
+    pub flow_ml_min: u16,
+}
+
+/// What the pump does when it stops hearing from the controller.
+///
+/// Every request addressed to the pump which it can decode restarts
+/// the timeout, `Heartbeat` is only needed when there is nothing else
+/// to send.
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Failsafe {
+    /// How long the pump waits for a request, zero disables the failsafe.
+    pub timeout_ms: u16,
+    /// The state the pump reverts to once the timeout passes.
+    pub safe_state: PumpState,
+}
+
+impl Failsafe {
+    pub const DISABLED: Self = Self {
+        timeout_ms: 0,
+        safe_state: PumpState::Off,
+    };
+}
+
+#[cfg(feature = "std")]
+text::named!(PumpState {
+    On => "on",
+    Off => "off",
+});
+
+/// `120mA 3000rpm 4500ml/min`
+#[cfg(feature = "std")]
+impl core::fmt::Display for Telemetry {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}mA {}rpm {}ml/min",
+            self.current_ma, self.rpm, self.flow_ml_min
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Telemetry {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "telemetry like `120mA 3000rpm 4500ml/min`";
+
+        let mut readings = s.split_whitespace();
+        let mut reading = |unit: &str| {
+            let value = readings
+                .next()
+                .and_then(|reading| reading.strip_suffix(unit))
+                .ok_or(text::ParseError::new(EXPECTED))?;
+
+            text::parse(value, EXPECTED)
+        };
+
+        let telemetry = Self {
+            current_ma: reading("mA")?,
+            rpm: reading("rpm")?,
+            flow_ml_min: reading("ml/min")?,
+        };
+
+        match readings.next() {
+            None => Ok(telemetry),
+            Some(_) => Err(text::ParseError::new(EXPECTED)),
+        }
+    }
+}
+
+/// `on after 1000ms`
+#[cfg(feature = "std")]
+impl core::fmt::Display for Failsafe {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} after {}ms", self.safe_state, self.timeout_ms)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Failsafe {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "a failsafe like `on after 1000ms`";
+
+        let (safe_state, timeout) = s
+            .split_once(" after ")
+            .ok_or(text::ParseError::new(EXPECTED))?;
+        let timeout_ms = timeout
+            .trim()
+            .strip_suffix("ms")
+            .ok_or(text::ParseError::new(EXPECTED))?;
+
+        Ok(Self {
+            timeout_ms: text::parse(timeout_ms, EXPECTED)?,
+            safe_state: text::parse(safe_state, EXPECTED)?,
+        })
+    }
+}
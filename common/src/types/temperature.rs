@@ -1 +1,270 @@
-pub type Temperature = i8;
+use cookie_cutter::encoding::vanilla;
+
+#[cfg(feature = "std")]
+use crate::text;
+
+/// A temperature in hundredths of a degree Celsius.
+///
+/// Covers -327.68 °C to 327.67 °C with 0.01 °C resolution.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Temperature(i16);
+
+impl Temperature {
+    pub const MIN: Self = Self(i16::MIN);
+    pub const MAX: Self = Self(i16::MAX);
+    pub const ZERO: Self = Self(0);
+
+    /// Offset between the Kelvin and Celsius scales in hundredths of a degree.
+    const KELVIN_OFFSET: i32 = 27315;
+
+    pub const fn from_centi_celsius(centi_celsius: i16) -> Self {
+        Self(centi_celsius)
+    }
+
+    /// Saturates at [`Temperature::MIN`] and [`Temperature::MAX`].
+    pub const fn from_celsius(celsius: i16) -> Self {
+        Self(celsius.saturating_mul(100))
+    }
+
+    pub const fn as_centi_celsius(self) -> i16 {
+        self.0
+    }
+
+    /// Whole degrees, rounded towards zero.
+    pub const fn as_celsius(self) -> i16 {
+        self.0 / 100
+    }
+
+    /// Rounded to the nearest hundredth, so converting
+    /// to Fahrenheit and back is lossless.
+    ///
+    /// Returns [`None`] if the temperature is out of range.
+    pub fn from_centi_fahrenheit(centi_fahrenheit: i32) -> Option<Self> {
+        let centi_celsius = div_round(centi_fahrenheit.checked_sub(3200)?.checked_mul(5)?, 9);
+
+        i16::try_from(centi_celsius).ok().map(Self)
+    }
+
+    /// Rounded to the nearest hundredth.
+    pub const fn as_centi_fahrenheit(self) -> i32 {
+        div_round(self.0 as i32 * 9, 5) + 3200
+    }
+
+    /// Returns [`None`] if the temperature is out of range.
+    pub fn from_centi_kelvin(centi_kelvin: i32) -> Option<Self> {
+        let centi_celsius = centi_kelvin.checked_sub(Self::KELVIN_OFFSET)?;
+
+        i16::try_from(centi_celsius).ok().map(Self)
+    }
+
+    pub const fn as_centi_kelvin(self) -> i32 {
+        self.0 as i32 + Self::KELVIN_OFFSET
+    }
+
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_add(rhs.0) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_sub(rhs.0) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    pub const fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
+    pub const fn saturating_sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+/// Divides by a positive `rhs`, rounding halves away from zero.
+const fn div_round(lhs: i32, rhs: i32) -> i32 {
+    if lhs < 0 {
+        (lhs - rhs / 2) / rhs
+    } else {
+        (lhs + rhs / 2) / rhs
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Temperature {
+    fn format(&self, fmt: defmt::Formatter) {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+
+        defmt::write!(
+            fmt,
+            "{=str}{}.{}{}C",
+            sign,
+            abs / 100,
+            abs / 10 % 10,
+            abs % 10
+        )
+    }
+}
+
+impl core::fmt::Debug for Temperature {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+
+        write!(f, "{}{}.{:02}C", sign, abs / 100, abs % 100)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for Temperature {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Degrees Celsius with up to two decimals, optionally followed by `C`,
+/// e.g. `21.5C` or `-3`.
+#[cfg(feature = "std")]
+impl core::str::FromStr for Temperature {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "a temperature like `21.50C`";
+
+        let s = s.trim();
+        let s = s.strip_suffix('C').unwrap_or(s);
+
+        let centi_celsius = text::parse_fixed(s, 2, EXPECTED)?;
+
+        i16::try_from(centi_celsius)
+            .map(Self)
+            .map_err(|_| text::ParseError::new(EXPECTED))
+    }
+}
+
+/// A gain in parts per ten thousand.
+///
+/// Covers 0 to 6.5535 with 0.0001 resolution.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Gain(u16);
+
+impl Gain {
+    pub const UNITY: Self = Self(10_000);
+
+    pub const fn from_per_ten_thousand(per_ten_thousand: u16) -> Self {
+        Self(per_ten_thousand)
+    }
+
+    pub const fn as_per_ten_thousand(self) -> u16 {
+        self.0
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Gain {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "{}.{}{}{}{}",
+            self.0 / 10_000,
+            self.0 / 1000 % 10,
+            self.0 / 100 % 10,
+            self.0 / 10 % 10,
+            self.0 % 10
+        )
+    }
+}
+
+impl core::fmt::Debug for Gain {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{:04}", self.0 / 10_000, self.0 % 10_000)
+    }
+}
+
+/// `1.0020`
+#[cfg(feature = "std")]
+impl core::fmt::Display for Gain {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Gain {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "a gain like `1.0020`";
+
+        u16::try_from(text::parse_fixed(s, 4, EXPECTED)?)
+            .map(Self)
+            .map_err(|_| text::ParseError::new(EXPECTED))
+    }
+}
+
+/// Corrects the readings of a probe.
+///
+/// The calibrated reading is the raw one scaled
+/// by `gain`, then shifted by `offset`.
+#[derive(Clone, Copy, Debug, PartialEq, vanilla::SerializeIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Calibration {
+    pub offset: Temperature,
+    pub gain: Gain,
+}
+
+impl Calibration {
+    /// Leaves readings as they are.
+    pub const IDENTITY: Self = Self {
+        offset: Temperature::ZERO,
+        gain: Gain::UNITY,
+    };
+
+    /// Saturates at [`Temperature::MIN`] and [`Temperature::MAX`].
+    pub const fn apply(self, raw: Temperature) -> Temperature {
+        let scaled = raw.0 as i32 * self.gain.0 as i32 / Gain::UNITY.0 as i32;
+        let calibrated = scaled + self.offset.0 as i32;
+
+        if calibrated > i16::MAX as i32 {
+            Temperature::MAX
+        } else if calibrated < i16::MIN as i32 {
+            Temperature::MIN
+        } else {
+            Temperature(calibrated as i16)
+        }
+    }
+}
+
+/// `gain 1.0020 offset -0.15C`
+#[cfg(feature = "std")]
+impl core::fmt::Display for Calibration {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "gain {} offset {}", self.gain, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Calibration {
+    type Err = text::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED: &str = "a calibration like `gain 1.0020 offset -0.15C`";
+
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["gain", gain, "offset", offset] => Ok(Self {
+                offset: text::parse(offset, EXPECTED)?,
+                gain: text::parse(gain, EXPECTED)?,
+            }),
+            _ => Err(text::ParseError::new(EXPECTED)),
+        }
+    }
+}
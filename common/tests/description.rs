@@ -0,0 +1,26 @@
+use common::command::{description::Message, pump, temperature};
+
+/// Opcodes are unique within a direction by construction,
+/// but a message travelling in the wrong direction must not
+/// be mistaken for another one either.
+fn assert_unique_opcodes(messages: &[Message]) {
+    for (i, a) in messages.iter().enumerate() {
+        for b in &messages[i + 1..] {
+            assert_ne!(
+                a.opcode, b.opcode,
+                "{} and {} share opcode {:#04x}",
+                a.name, b.name, a.opcode
+            );
+        }
+    }
+}
+
+#[test]
+fn pump_opcodes_are_unique() {
+    assert_unique_opcodes(pump::MESSAGES);
+}
+
+#[test]
+fn temperature_opcodes_are_unique() {
+    assert_unique_opcodes(temperature::MESSAGES);
+}
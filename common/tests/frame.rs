@@ -0,0 +1,108 @@
+use common::{
+    command::pump::{FromPeripheral, ToPeripheral},
+    frame::{self, Error, DELIMITER, MAX_FRAME},
+    types::pump::PumpState,
+};
+
+fn encode(message: &impl cookie_cutter::SerializeIter) -> Vec<u8> {
+    let mut buf = [0; MAX_FRAME];
+    let n = frame::encode(message, &mut buf).unwrap();
+
+    buf[..n].to_vec()
+}
+
+#[test]
+fn round_trip() {
+    let bytes = encode(&ToPeripheral::Set(PumpState::On));
+
+    assert_eq!(bytes.first(), Some(&DELIMITER));
+    assert_eq!(bytes.last(), Some(&DELIMITER));
+    assert!(!bytes[1..bytes.len() - 1].contains(&DELIMITER));
+
+    assert!(matches!(
+        frame::decode(&bytes),
+        Ok(ToPeripheral::Set(PumpState::On))
+    ));
+}
+
+#[test]
+fn incomplete_frame() {
+    let bytes = encode(&ToPeripheral::Get);
+
+    assert!(matches!(
+        frame::decode::<ToPeripheral>(&bytes[..bytes.len() - 1]),
+        Err(Error::EndOfInput)
+    ));
+}
+
+#[test]
+fn resync_after_garbage() {
+    let mut stream = vec![0x13, 0x37, 0xca, 0x5e, 0xff];
+    stream.extend(encode(&FromPeripheral::PumpState(PumpState::Off)));
+
+    let mut iter = stream.iter();
+
+    assert!(matches!(
+        frame::decode::<FromPeripheral>(&mut iter),
+        Err(Error::Corrupt)
+    ));
+    assert!(matches!(
+        frame::decode(&mut iter),
+        Ok(FromPeripheral::PumpState(PumpState::Off))
+    ));
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn resync_after_bit_flip() {
+    let mut stream = encode(&FromPeripheral::PumpState(PumpState::On));
+    stream[3] ^= 0x04;
+    stream.extend(encode(&FromPeripheral::PumpState(PumpState::Off)));
+
+    let mut iter = stream.iter();
+
+    assert!(matches!(
+        frame::decode::<FromPeripheral>(&mut iter),
+        Err(Error::Corrupt)
+    ));
+    assert!(matches!(
+        frame::decode(&mut iter),
+        Ok(FromPeripheral::PumpState(PumpState::Off))
+    ));
+}
+
+#[test]
+fn resync_after_truncated_frame() {
+    let first = encode(&ToPeripheral::Set(PumpState::On));
+
+    // the tail of the first frame is lost on the line
+    let mut stream = first[..first.len() - 3].to_vec();
+    stream.extend(encode(&ToPeripheral::Get));
+
+    let mut iter = stream.iter();
+
+    assert!(matches!(
+        frame::decode::<ToPeripheral>(&mut iter),
+        Err(Error::Corrupt)
+    ));
+    assert!(matches!(frame::decode(&mut iter), Ok(ToPeripheral::Get)));
+}
+
+#[test]
+fn resync_after_long_garbage() {
+    let mut stream = vec![0xa5; 3 * MAX_FRAME];
+    stream.extend(encode(&ToPeripheral::Get));
+
+    let mut iter = stream.iter();
+
+    let mut dropped = 0;
+    let result = loop {
+        match frame::decode::<ToPeripheral>(&mut iter) {
+            Err(Error::Corrupt) => dropped += 1,
+            result => break result,
+        }
+    };
+
+    assert!(dropped > 0);
+    assert!(matches!(result, Ok(ToPeripheral::Get)));
+}
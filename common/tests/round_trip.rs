@@ -0,0 +1,238 @@
+use std::fmt::Debug;
+
+use common::{
+    command::{pump, temperature, NackReason},
+    types::{
+        device::{Identity, Kind, Version},
+        link::BaudRate,
+        pump::{Failsafe, PumpState, Speed, Telemetry},
+        temperature::{Calibration, Gain, Temperature},
+    },
+};
+use cookie_cutter::SerializeIter;
+use proptest::{prelude::*, strategy::LazyJust};
+
+/// Larger than any message.
+const BUF_LEN: usize = 64;
+
+fn round_trip<T: SerializeIter + PartialEq + Debug>(value: T) -> Result<(), TestCaseError> {
+    let mut buf = [0; BUF_LEN];
+
+    prop_assert!(value.serialize_iter(buf.iter_mut()).is_ok());
+
+    let Ok(decoded) = T::deserialize_iter(buf.iter()) else {
+        return Err(TestCaseError::fail(format!(
+            "failed to deserialize {value:?}"
+        )));
+    };
+
+    prop_assert_eq!(decoded, value);
+
+    Ok(())
+}
+
+fn pump_state() -> impl Strategy<Value = PumpState> {
+    prop_oneof![Just(PumpState::On), Just(PumpState::Off)]
+}
+
+fn speed() -> impl Strategy<Value = Speed> {
+    (0..=Speed::FULL.as_percent()).prop_map(Speed::from_percent)
+}
+
+fn telemetry() -> impl Strategy<Value = Telemetry> {
+    any::<(u16, u16, u16)>().prop_map(|(current_ma, rpm, flow_ml_min)| Telemetry {
+        current_ma,
+        rpm,
+        flow_ml_min,
+    })
+}
+
+fn failsafe() -> impl Strategy<Value = Failsafe> {
+    (any::<u16>(), pump_state()).prop_map(|(timeout_ms, safe_state)| Failsafe {
+        timeout_ms,
+        safe_state,
+    })
+}
+
+fn identity() -> impl Strategy<Value = Identity> {
+    (
+        prop_oneof![Just(Kind::Temperature), Just(Kind::Pump)],
+        any::<(u8, u8, u8, u8)>(),
+    )
+        .prop_map(|(kind, (protocol, major, minor, patch))| Identity {
+            kind,
+            protocol,
+            firmware: Version {
+                major,
+                minor,
+                patch,
+            },
+        })
+}
+
+fn temperature() -> impl Strategy<Value = Temperature> {
+    any::<i16>().prop_map(Temperature::from_centi_celsius)
+}
+
+fn gain() -> impl Strategy<Value = Gain> {
+    any::<u16>().prop_map(Gain::from_per_ten_thousand)
+}
+
+fn calibration() -> impl Strategy<Value = Calibration> {
+    (temperature(), gain()).prop_map(|(offset, gain)| Calibration { offset, gain })
+}
+
+fn baud_rate() -> impl Strategy<Value = BaudRate> {
+    prop_oneof![
+        Just(BaudRate::Bps9600),
+        Just(BaudRate::Bps19200),
+        Just(BaudRate::Bps38400),
+        Just(BaudRate::Bps57600),
+        Just(BaudRate::Bps115200),
+    ]
+}
+
+fn nack_reason() -> impl Strategy<Value = NackReason> {
+    prop_oneof![
+        Just(NackReason::UnknownOpcode),
+        Just(NackReason::BadPayload),
+        Just(NackReason::Busy),
+        Just(NackReason::NotReady),
+    ]
+}
+
+fn pump_fault() -> impl Strategy<Value = pump::Fault> {
+    use pump::Fault;
+
+    prop_oneof![
+        Just(Fault::Temperature),
+        Just(Fault::Current),
+        Just(Fault::Stall),
+        Just(Fault::DryRun),
+        Just(Fault::OverVoltage),
+        Just(Fault::Internal),
+    ]
+}
+
+fn temperature_fault() -> impl Strategy<Value = temperature::Fault> {
+    use temperature::Fault;
+
+    prop_oneof![
+        Just(Fault::OpenCircuit),
+        Just(Fault::ShortCircuit),
+        Just(Fault::OutOfRange),
+        Just(Fault::NotReady),
+    ]
+}
+
+fn pump_to_peripheral() -> impl Strategy<Value = pump::ToPeripheral> {
+    use pump::ToPeripheral;
+
+    prop_oneof![
+        pump_state().prop_map(ToPeripheral::Set),
+        Just(ToPeripheral::Get),
+        speed().prop_map(ToPeripheral::SetSpeed),
+        Just(ToPeripheral::GetTelemetry),
+        Just(ToPeripheral::Identify),
+        Just(ToPeripheral::ClearFault),
+        failsafe().prop_map(ToPeripheral::ConfigureFailsafe),
+        Just(ToPeripheral::Heartbeat),
+        baud_rate().prop_map(ToPeripheral::SetBaud),
+    ]
+}
+
+fn pump_from_peripheral() -> impl Strategy<Value = pump::FromPeripheral> {
+    use pump::FromPeripheral;
+
+    prop_oneof![
+        pump_state().prop_map(FromPeripheral::PumpState),
+        pump_fault().prop_map(FromPeripheral::Fault),
+        identity().prop_map(FromPeripheral::Identity),
+        speed().prop_map(FromPeripheral::Speed),
+        telemetry().prop_map(FromPeripheral::Telemetry),
+        failsafe().prop_map(FromPeripheral::Failsafe),
+        nack_reason().prop_map(|reason| FromPeripheral::Nack { reason }),
+        baud_rate().prop_map(FromPeripheral::Baud),
+    ]
+}
+
+fn temperature_to_peripheral() -> impl Strategy<Value = temperature::ToPeripheral> {
+    use temperature::ToPeripheral;
+
+    prop_oneof![
+        Just(ToPeripheral::Read),
+        Just(ToPeripheral::Identify),
+        any::<u16>().prop_map(|period_ms| ToPeripheral::Subscribe { period_ms }),
+        Just(ToPeripheral::Unsubscribe),
+        baud_rate().prop_map(ToPeripheral::SetBaud),
+        calibration().prop_map(ToPeripheral::SetCalibration),
+        Just(ToPeripheral::GetCalibration),
+    ]
+}
+
+fn temperature_from_peripheral() -> impl Strategy<Value = temperature::FromPeripheral> {
+    use temperature::FromPeripheral;
+
+    prop_oneof![
+        temperature().prop_map(FromPeripheral::Temperature),
+        identity().prop_map(FromPeripheral::Identity),
+        temperature_fault().prop_map(FromPeripheral::Fault),
+        any::<u16>().prop_map(|period_ms| FromPeripheral::Subscribed { period_ms }),
+        // responses are not `Clone`, which `Just` needs
+        LazyJust::new(|| FromPeripheral::Unsubscribed),
+        nack_reason().prop_map(|reason| FromPeripheral::Nack { reason }),
+        baud_rate().prop_map(FromPeripheral::Baud),
+        calibration().prop_map(FromPeripheral::Calibration),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn nack_reason_round_trip(value in nack_reason()) {
+        round_trip(value)?;
+    }
+
+    #[test]
+    fn pump_state_round_trip(value in pump_state()) {
+        round_trip(value)?;
+    }
+
+    #[test]
+    fn pump_fault_round_trip(value in pump_fault()) {
+        round_trip(value)?;
+    }
+
+    #[test]
+    fn temperature_fault_round_trip(value in temperature_fault()) {
+        round_trip(value)?;
+    }
+
+    #[test]
+    fn pump_to_peripheral_round_trip(value in pump_to_peripheral()) {
+        round_trip(value)?;
+    }
+
+    #[test]
+    fn pump_from_peripheral_round_trip(value in pump_from_peripheral()) {
+        round_trip(value)?;
+    }
+
+    #[test]
+    fn temperature_to_peripheral_round_trip(value in temperature_to_peripheral()) {
+        round_trip(value)?;
+    }
+
+    #[test]
+    fn temperature_from_peripheral_round_trip(value in temperature_from_peripheral()) {
+        round_trip(value)?;
+    }
+
+    /// Any byte decodes, so the peripheral has to check it.
+    #[test]
+    fn speed_validity(percent in any::<u8>()) {
+        let speed = Speed::deserialize_iter([percent].iter()).unwrap();
+
+        prop_assert_eq!(speed.is_valid(), percent <= 100);
+        prop_assert_eq!(Speed::try_from(percent).is_ok(), percent <= 100);
+    }
+}
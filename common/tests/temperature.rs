@@ -0,0 +1,93 @@
+use common::types::temperature::Temperature;
+
+#[test]
+fn fahrenheit() {
+    assert_eq!(Temperature::ZERO.as_centi_fahrenheit(), 3200);
+    assert_eq!(Temperature::from_celsius(100).as_centi_fahrenheit(), 21200);
+    assert_eq!(Temperature::from_celsius(-40).as_centi_fahrenheit(), -4000);
+
+    assert_eq!(
+        Temperature::from_centi_fahrenheit(21200),
+        Some(Temperature::from_celsius(100))
+    );
+    assert_eq!(
+        Temperature::from_centi_fahrenheit(-4000),
+        Some(Temperature::from_celsius(-40))
+    );
+
+    // 0.01 °C is 0.018 °F, rounded up
+    assert_eq!(
+        Temperature::from_centi_celsius(1).as_centi_fahrenheit(),
+        3202
+    );
+    assert_eq!(
+        Temperature::from_centi_celsius(-1).as_centi_fahrenheit(),
+        3198
+    );
+}
+
+#[test]
+fn fahrenheit_round_trip() {
+    for centi_celsius in i16::MIN..=i16::MAX {
+        let temperature = Temperature::from_centi_celsius(centi_celsius);
+
+        assert_eq!(
+            Temperature::from_centi_fahrenheit(temperature.as_centi_fahrenheit()),
+            Some(temperature)
+        );
+    }
+}
+
+#[test]
+fn kelvin() {
+    assert_eq!(Temperature::ZERO.as_centi_kelvin(), 27315);
+    assert_eq!(
+        Temperature::from_centi_kelvin(0),
+        Some(Temperature::from_centi_celsius(-27315))
+    );
+
+    for temperature in [Temperature::MIN, Temperature::ZERO, Temperature::MAX] {
+        assert_eq!(
+            Temperature::from_centi_kelvin(temperature.as_centi_kelvin()),
+            Some(temperature)
+        );
+    }
+}
+
+#[test]
+fn out_of_range_conversions() {
+    let max = Temperature::MAX.as_centi_kelvin();
+    let min = Temperature::MIN.as_centi_kelvin();
+
+    assert_eq!(Temperature::from_centi_kelvin(max + 1), None);
+    assert_eq!(Temperature::from_centi_kelvin(min - 1), None);
+
+    // well past the range, and past what the arithmetic fits
+    assert_eq!(Temperature::from_centi_fahrenheit(1_000_000), None);
+    assert_eq!(Temperature::from_centi_fahrenheit(i32::MAX), None);
+    assert_eq!(Temperature::from_centi_fahrenheit(i32::MIN), None);
+}
+
+#[test]
+fn arithmetic() {
+    let one = Temperature::from_celsius(1);
+
+    assert_eq!(
+        Temperature::from_celsius(20).checked_add(one),
+        Some(Temperature::from_celsius(21))
+    );
+    assert_eq!(
+        Temperature::from_celsius(20).checked_sub(one),
+        Some(Temperature::from_celsius(19))
+    );
+
+    assert_eq!(Temperature::MAX.checked_add(one), None);
+    assert_eq!(Temperature::MIN.checked_sub(one), None);
+
+    assert_eq!(Temperature::MAX.saturating_add(one), Temperature::MAX);
+    assert_eq!(Temperature::MIN.saturating_sub(one), Temperature::MIN);
+
+    // whole degrees saturate too
+    assert_eq!(Temperature::from_celsius(400), Temperature::MAX);
+    assert_eq!(Temperature::from_celsius(-400), Temperature::MIN);
+}
@@ -0,0 +1,131 @@
+use std::{fmt::Display, str::FromStr};
+
+use common::{
+    command::{pump, temperature, NackReason},
+    types::{
+        device::{Identity, Kind, Version},
+        pump::{Failsafe, PumpState, Speed, Telemetry},
+        temperature::{Calibration, Gain, Temperature},
+    },
+};
+
+fn round_trip<T>(value: T, text: &str)
+where
+    T: Display + FromStr + PartialEq + std::fmt::Debug,
+    T::Err: std::fmt::Debug,
+{
+    assert_eq!(value.to_string(), text);
+    assert_eq!(text.parse::<T>().unwrap(), value);
+}
+
+#[test]
+fn types() {
+    round_trip(PumpState::On, "on");
+    round_trip(Speed::from_percent(42), "42%");
+    round_trip(
+        Telemetry {
+            current_ma: 120,
+            rpm: 3000,
+            flow_ml_min: 4500,
+        },
+        "120mA 3000rpm 4500ml/min",
+    );
+    round_trip(
+        Failsafe {
+            timeout_ms: 1000,
+            safe_state: PumpState::On,
+        },
+        "on after 1000ms",
+    );
+    round_trip(
+        Identity {
+            kind: Kind::Pump,
+            protocol: 7,
+            firmware: Version {
+                major: 0,
+                minor: 1,
+                patch: 0,
+            },
+        },
+        "pump protocol 7 firmware 0.1.0",
+    );
+    round_trip(Temperature::from_centi_celsius(-305), "-3.05C");
+    round_trip(Temperature::MIN, "-327.68C");
+    round_trip(
+        Calibration {
+            offset: Temperature::from_centi_celsius(-15),
+            gain: Gain::from_per_ten_thousand(10_020),
+        },
+        "gain 1.0020 offset -0.15C",
+    );
+    round_trip(NackReason::BadPayload, "bad-payload");
+    round_trip(pump::Fault::DryRun, "dry-run");
+    round_trip(temperature::Fault::NotReady, "not-ready");
+}
+
+#[test]
+fn lenient_input() {
+    assert_eq!("42".parse(), Ok(Speed::from_percent(42)));
+    assert_eq!("21.5".parse(), Ok(Temperature::from_centi_celsius(2150)));
+    assert_eq!(" off ".parse(), Ok(PumpState::Off));
+}
+
+#[test]
+fn invalid_input() {
+    assert!("101%".parse::<Speed>().is_err());
+    assert_eq!(Speed::try_from_percent(101), None);
+    assert!("327.68C".parse::<Temperature>().is_err());
+    assert!("1.234C".parse::<Temperature>().is_err());
+    assert!("+1C".parse::<Temperature>().is_err());
+    assert!("1.2".parse::<Version>().is_err());
+    assert!("6.5536".parse::<Gain>().is_err());
+    assert!("Set".parse::<pump::ToPeripheral>().is_err());
+    assert!("Get(on)".parse::<pump::ToPeripheral>().is_err());
+    assert!("Set(on, off)".parse::<pump::ToPeripheral>().is_err());
+    assert!("Launch".parse::<pump::ToPeripheral>().is_err());
+}
+
+#[test]
+fn commands() {
+    round_trip(pump::ToPeripheral::Get, "Get");
+    round_trip(pump::ToPeripheral::Set(PumpState::Off), "Set(off)");
+    round_trip(
+        pump::FromPeripheral::Nack {
+            reason: NackReason::Busy,
+        },
+        "Nack { reason: busy }",
+    );
+    round_trip(
+        temperature::ToPeripheral::Subscribe { period_ms: 1000 },
+        "Subscribe { period_ms: 1000 }",
+    );
+    round_trip(
+        temperature::FromPeripheral::Temperature(Temperature::from_celsius(21)),
+        "Temperature(21.00C)",
+    );
+    round_trip(
+        temperature::ToPeripheral::SetCalibration(Calibration {
+            offset: Temperature::from_centi_celsius(-15),
+            gain: Gain::from_per_ten_thousand(10_020),
+        }),
+        "SetCalibration(gain 1.0020 offset -0.15C)",
+    );
+}
+
+#[test]
+fn envelopes() {
+    let request: pump::Request = "@32 #3 SetSpeed(50%)".parse().unwrap();
+
+    assert_eq!(request.address, 0x20);
+    assert_eq!(request.id, 3);
+    assert_eq!(
+        request.command,
+        pump::ToPeripheral::SetSpeed(Speed::from_percent(50))
+    );
+    assert_eq!(request.to_string(), "@32 #3 SetSpeed(50%)");
+
+    let response: temperature::Response = "@16 #4 Unsubscribed".parse().unwrap();
+
+    assert_eq!(response.command, temperature::FromPeripheral::Unsubscribed);
+    assert_eq!(response.to_string(), "@16 #4 Unsubscribed");
+}
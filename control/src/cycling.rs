@@ -0,0 +1,143 @@
+use common::types::pump::PumpState;
+
+/// Starts remembered at most, so also the highest
+/// usable [`Limits::max_starts_per_hour`].
+pub const MAX_STARTS: usize = 32;
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+/// How often the pump may switch without being damaged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Limits {
+    /// How long the pump runs at least once started.
+    pub min_on_ms: u32,
+    /// How long the pump rests at least once stopped.
+    pub min_off_ms: u32,
+    /// Starts within any hour, at most [`MAX_STARTS`],
+    /// zero for no limit.
+    pub max_starts_per_hour: u8,
+}
+
+/// [`Limits::max_starts_per_hour`] over [`MAX_STARTS`],
+/// more than the guard can remember.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TooManyStarts {
+    pub max_starts_per_hour: u8,
+}
+
+/// Why a transition has to wait, and for how long.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Deferral {
+    MinOnTime { remaining_ms: u64 },
+    MinOffTime { remaining_ms: u64 },
+    StartLimit { remaining_ms: u64 },
+}
+
+/// Holds back transitions which would cycle the pump too fast.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Guard {
+    limits: Limits,
+    /// The state of the pump and since when.
+    state: Option<(PumpState, u64)>,
+    /// The most recent starts, `next_start` is the oldest once full.
+    starts: [u64; MAX_STARTS],
+    next_start: usize,
+    recorded_starts: usize,
+}
+
+impl Guard {
+    pub const fn new(limits: Limits) -> Result<Self, TooManyStarts> {
+        if limits.max_starts_per_hour as usize > MAX_STARTS {
+            return Err(TooManyStarts {
+                max_starts_per_hour: limits.max_starts_per_hour,
+            });
+        }
+
+        Ok(Self {
+            limits,
+            state: None,
+            starts: [0; MAX_STARTS],
+            next_start: 0,
+            recorded_starts: 0,
+        })
+    }
+
+    /// Note the state the pump was in at `at_ms`.
+    ///
+    /// Must be called in order of time.
+    pub fn record(&mut self, at_ms: u64, state: PumpState) {
+        if let Some((current, _)) = self.state {
+            if current == state {
+                return;
+            }
+        }
+
+        self.state = Some((state, at_ms));
+
+        if let PumpState::On = state {
+            self.starts[self.next_start] = at_ms;
+            self.next_start = (self.next_start + 1) % MAX_STARTS;
+            self.recorded_starts = (self.recorded_starts + 1).min(MAX_STARTS);
+        }
+    }
+
+    /// [`Guard::check`] `requested`, and if it is allowed
+    /// [`Guard::record`] it right away, so the transition
+    /// counts before the pump reports being switched.
+    pub fn request(&mut self, now_ms: u64, requested: PumpState) -> Result<(), Deferral> {
+        self.check(now_ms, requested)?;
+        self.record(now_ms, requested);
+
+        Ok(())
+    }
+
+    /// Whether the pump may be switched to `requested` at `now_ms`.
+    pub fn check(&self, now_ms: u64, requested: PumpState) -> Result<(), Deferral> {
+        let Some((current, since_ms)) = self.state else {
+            return Ok(());
+        };
+
+        if requested == current {
+            return Ok(());
+        }
+
+        let elapsed_ms = now_ms.saturating_sub(since_ms);
+
+        match current {
+            PumpState::On if elapsed_ms < self.limits.min_on_ms as u64 => {
+                return Err(Deferral::MinOnTime {
+                    remaining_ms: self.limits.min_on_ms as u64 - elapsed_ms,
+                });
+            }
+            PumpState::Off if elapsed_ms < self.limits.min_off_ms as u64 => {
+                return Err(Deferral::MinOffTime {
+                    remaining_ms: self.limits.min_off_ms as u64 - elapsed_ms,
+                });
+            }
+            _ => {}
+        }
+
+        let max_starts = self.limits.max_starts_per_hour as usize;
+
+        if let (PumpState::On, 1..) = (requested, max_starts) {
+            // the start which would drop out of the rolling hour
+            // once this one is made, if the limit is reached
+            if self.recorded_starts >= max_starts {
+                let index = (self.next_start + MAX_STARTS - max_starts) % MAX_STARTS;
+                let since_ms = now_ms.saturating_sub(self.starts[index]);
+
+                if since_ms < HOUR_MS {
+                    return Err(Deferral::StartLimit {
+                        remaining_ms: HOUR_MS - since_ms,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use common::types::{pump::PumpState, temperature::Temperature};
+
+/// Bands around the setpoint the temperature has to leave
+/// before an on/off controller switches the pump.
+///
+/// The pump turns on above `setpoint + upper` and off below
+/// `setpoint - lower`, in between it stays as it is, so noise
+/// narrower than the bands together cannot make it flip.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hysteresis {
+    pub upper: Temperature,
+    pub lower: Temperature,
+}
+
+impl Hysteresis {
+    /// Switch as soon as the temperature crosses the setpoint.
+    pub const NONE: Self = Self {
+        upper: Temperature::ZERO,
+        lower: Temperature::ZERO,
+    };
+
+    /// Which state the pump in state `current` should be in.
+    pub fn target(
+        &self,
+        setpoint: Temperature,
+        temperature: Temperature,
+        current: PumpState,
+    ) -> PumpState {
+        if temperature > setpoint.saturating_add(self.upper) {
+            PumpState::On
+        } else if temperature < setpoint.saturating_sub(self.lower) {
+            PumpState::Off
+        } else {
+            current
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Control algorithms for the model, free of any hardware
+//! so they can be tested on the host.
+//!
+//! Timestamps throughout are in milliseconds, as a `u64`,
+//! since any fixed point in time.
+
+#![no_std]
+
+pub mod cycling;
+pub mod hysteresis;
+pub mod pid;
+pub mod schedule;
+pub mod setpoint;
+
+use hysteresis::Hysteresis;
+use pid::{Drive, Pid};
+
+/// How temperatures are turned into pump commands.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Control {
+    /// Full speed above the target, off below,
+    /// switching only once outside the bands.
+    OnOff { hysteresis: Hysteresis },
+    /// A PID controller whose output, in percent, drives the pump.
+    Pid { pid: Pid, drive: Drive },
+}
@@ -0,0 +1,119 @@
+/// Which way the output moves as the measurement rises.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Action {
+    /// The output rises as the measurement falls below the setpoint,
+    /// like a heater.
+    Direct,
+    /// The output rises as the measurement rises above the setpoint,
+    /// like a cooling pump.
+    Reverse,
+}
+
+/// How the output of a [`Pid`] controller drives the pump.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Drive {
+    /// As its speed.
+    Speed,
+    /// As the share of every window the pump runs at full speed,
+    /// for pumps without speed control.
+    TimeProportioned { window_ms: u32 },
+}
+
+/// Gains and limits of a [`Pid`] controller.
+///
+/// Gains are per unit of error, with the integral
+/// and derivative terms in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub action: Action,
+    pub output_min: f32,
+    pub output_max: f32,
+}
+
+/// A discrete PID controller.
+///
+/// The output is clamped to the configured limits, the integrator
+/// stops accumulating while the output is saturated in the direction
+/// the error pushes it, and the derivative acts on the measurement
+/// alone so setpoint changes do not kick the output.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Pid {
+    config: Config,
+    integral: f32,
+    last_measurement: Option<f32>,
+}
+
+impl Pid {
+    pub const fn new(config: Config) -> Self {
+        Self {
+            config,
+            integral: 0.,
+            last_measurement: None,
+        }
+    }
+
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Forget the accumulated integral and the last measurement,
+    /// e.g. after a gap in the measurements.
+    pub fn reset(&mut self) {
+        self.integral = 0.;
+        self.last_measurement = None;
+    }
+
+    /// Advance the controller by `dt_ms` with a new measurement.
+    ///
+    /// The first update after [`Pid::new`] or [`Pid::reset`]
+    /// has no derivative term, as there is no previous measurement.
+    pub fn update(&mut self, setpoint: f32, measurement: f32, dt_ms: u32) -> f32 {
+        let Config {
+            kp,
+            ki,
+            kd,
+            action,
+            output_min,
+            output_max,
+        } = self.config;
+
+        // positive error asks for more output
+        let sign = match action {
+            Action::Direct => 1.,
+            Action::Reverse => -1.,
+        };
+
+        let error = sign * (setpoint - measurement);
+        let dt = dt_ms as f32 / 1000.;
+
+        let derivative = match self.last_measurement {
+            Some(last) if dt_ms != 0 => -sign * kd * (measurement - last) / dt,
+            _ => 0.,
+        };
+
+        self.last_measurement = Some(measurement);
+
+        let proportional = kp * error;
+        let integral = self.integral + ki * error * dt;
+
+        let unclamped = proportional + integral + derivative;
+
+        // anti-windup: hold the integral while saturated,
+        // unless the error is pulling the output back in
+        let winding_up =
+            (unclamped > output_max && error > 0.) || (unclamped < output_min && error < 0.);
+
+        if !winding_up {
+            self.integral = integral;
+        }
+
+        (proportional + self.integral + derivative).clamp(output_min, output_max)
+    }
+}
@@ -0,0 +1,199 @@
+use common::types::temperature::Temperature;
+
+/// A step of a ramp/soak profile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Segment {
+    /// Soak at `temperature` for `duration_ms`.
+    Hold {
+        temperature: Temperature,
+        duration_ms: u32,
+    },
+    /// Move evenly from where the previous segment
+    /// ended to `temperature` over `duration_ms`.
+    Ramp {
+        temperature: Temperature,
+        duration_ms: u32,
+    },
+}
+
+impl Segment {
+    /// The temperature the segment ends at.
+    pub const fn temperature(&self) -> Temperature {
+        match self {
+            Self::Hold { temperature, .. } | Self::Ramp { temperature, .. } => *temperature,
+        }
+    }
+
+    pub const fn duration_ms(&self) -> u32 {
+        match self {
+            Self::Hold { duration_ms, .. } | Self::Ramp { duration_ms, .. } => *duration_ms,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Status {
+    Running,
+    Paused,
+    /// Past the last segment, holding its temperature.
+    Finished,
+}
+
+/// Where a schedule is at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Progress {
+    pub status: Status,
+    /// The current segment and its index in the profile.
+    pub index: usize,
+    pub segment: Segment,
+    /// The temperature to control to.
+    pub target: Temperature,
+    /// Time left in the current segment.
+    pub remaining_ms: u64,
+    /// Time left until the end of the profile.
+    pub total_remaining_ms: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Run {
+    Idle,
+    Running { since_ms: u64 },
+    Paused,
+}
+
+/// Runs a ramp/soak profile, producing the target temperature over time.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Schedule {
+    profile: &'static [Segment],
+    /// Where the first segment starts from.
+    from: Temperature,
+    /// Time spent running before the last resume.
+    elapsed_ms: u64,
+    run: Run,
+}
+
+impl Schedule {
+    /// No profile running.
+    pub const IDLE: Self = Self {
+        profile: &[],
+        from: Temperature::ZERO,
+        elapsed_ms: 0,
+        run: Run::Idle,
+    };
+
+    /// Run `profile` from its start, starting from `from`.
+    ///
+    /// A running profile is replaced.
+    pub fn start(&mut self, profile: &'static [Segment], from: Temperature, now_ms: u64) {
+        *self = Self {
+            profile,
+            from,
+            elapsed_ms: 0,
+            run: Run::Running { since_ms: now_ms },
+        };
+    }
+
+    /// Freeze the profile where it is, if running.
+    pub fn pause(&mut self, now_ms: u64) {
+        if let Run::Running { since_ms } = self.run {
+            self.elapsed_ms += now_ms.saturating_sub(since_ms);
+            self.run = Run::Paused;
+        }
+    }
+
+    /// Continue where the profile was paused, if paused.
+    pub fn resume(&mut self, now_ms: u64) {
+        if let Run::Paused = self.run {
+            self.run = Run::Running { since_ms: now_ms };
+        }
+    }
+
+    /// Stop the profile for good.
+    pub fn abort(&mut self) {
+        *self = Self::IDLE;
+    }
+
+    /// Where the profile is at `now_ms`, [`None`] if none is running.
+    pub fn progress(&self, now_ms: u64) -> Option<Progress> {
+        let (elapsed_ms, status) = match self.run {
+            Run::Idle => return None,
+            Run::Running { since_ms } => (
+                self.elapsed_ms + now_ms.saturating_sub(since_ms),
+                Status::Running,
+            ),
+            Run::Paused => (self.elapsed_ms, Status::Paused),
+        };
+
+        let total_ms: u64 = self
+            .profile
+            .iter()
+            .map(|segment| segment.duration_ms() as u64)
+            .sum();
+
+        let mut from = self.from;
+        let mut offset_ms = elapsed_ms;
+
+        for (index, segment) in self.profile.iter().enumerate() {
+            let duration_ms = segment.duration_ms() as u64;
+
+            if offset_ms < duration_ms {
+                let target = match segment {
+                    Segment::Hold { temperature, .. } => *temperature,
+                    Segment::Ramp { temperature, .. } => {
+                        interpolate(from, *temperature, offset_ms, duration_ms)
+                    }
+                };
+
+                return Some(Progress {
+                    status,
+                    index,
+                    segment: *segment,
+                    target,
+                    remaining_ms: duration_ms - offset_ms,
+                    total_remaining_ms: total_ms - elapsed_ms,
+                });
+            }
+
+            offset_ms -= duration_ms;
+            from = segment.temperature();
+        }
+
+        let index = self.profile.len().checked_sub(1)?;
+        let segment = &self.profile[index];
+
+        Some(Progress {
+            status: Status::Finished,
+            index,
+            segment: *segment,
+            target: segment.temperature(),
+            remaining_ms: 0,
+            total_remaining_ms: 0,
+        })
+    }
+
+    /// The temperature to control to at `now_ms`, [`None`] if no profile is running.
+    pub fn target(&self, now_ms: u64) -> Option<Temperature> {
+        self.progress(now_ms).map(|progress| progress.target)
+    }
+}
+
+/// The temperature `offset_ms` into a ramp from `from` to `to`.
+fn interpolate(
+    from: Temperature,
+    to: Temperature,
+    offset_ms: u64,
+    duration_ms: u64,
+) -> Temperature {
+    let from = from.as_centi_celsius() as i64;
+    let to = to.as_centi_celsius() as i64;
+
+    // between `from` and `to`, so in range
+    Temperature::from_centi_celsius(
+        (from + (to - from) * offset_ms as i64 / duration_ms as i64) as i16,
+    )
+}
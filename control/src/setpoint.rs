@@ -0,0 +1,143 @@
+use common::types::temperature::Temperature;
+
+/// The range setpoints are accepted in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Bounds {
+    pub min: Temperature,
+    pub max: Temperature,
+}
+
+impl Bounds {
+    pub const fn contains(&self, temperature: Temperature) -> bool {
+        let centi_celsius = temperature.as_centi_celsius();
+
+        self.min.as_centi_celsius() <= centi_celsius && centi_celsius <= self.max.as_centi_celsius()
+    }
+}
+
+/// A setpoint outside of the configured [`Bounds`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutOfBounds {
+    pub requested: Temperature,
+    pub bounds: Bounds,
+}
+
+/// A [`Setpoint`] which cannot be set up as configured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Invalid {
+    OutOfBounds(OutOfBounds),
+    /// A rate not above zero, the active setpoint
+    /// would never move towards a new one.
+    Rate(Temperature),
+}
+
+/// A setpoint which can be changed at runtime.
+///
+/// With a rate, the active setpoint moves towards a new final
+/// one by at most that much per minute, rather than jumping and
+/// making the control slam the pump on or off.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Setpoint {
+    bounds: Bounds,
+    /// How far the active setpoint moves per minute,
+    /// [`None`] to follow the final one at once.
+    rate: Option<Temperature>,
+    /// Where the active setpoint was when the final one changed, and when.
+    from: Temperature,
+    since_ms: u64,
+    target: Temperature,
+}
+
+impl Setpoint {
+    pub const fn new(
+        target: Temperature,
+        bounds: Bounds,
+        rate: Option<Temperature>,
+    ) -> Result<Self, Invalid> {
+        if !bounds.contains(target) {
+            return Err(Invalid::OutOfBounds(OutOfBounds {
+                requested: target,
+                bounds,
+            }));
+        }
+
+        if let Some(rate) = rate {
+            if rate.as_centi_celsius() <= 0 {
+                return Err(Invalid::Rate(rate));
+            }
+        }
+
+        Ok(Self {
+            bounds,
+            rate,
+            from: target,
+            since_ms: 0,
+            target,
+        })
+    }
+
+    /// Change the final setpoint at `now_ms`, the active
+    /// one ramps towards it from wherever it is.
+    pub fn set(&mut self, target: Temperature, now_ms: u64) -> Result<(), OutOfBounds> {
+        if !self.bounds.contains(target) {
+            return Err(OutOfBounds {
+                requested: target,
+                bounds: self.bounds,
+            });
+        }
+
+        self.from = self.active(now_ms);
+        self.since_ms = now_ms;
+        self.target = target;
+
+        Ok(())
+    }
+
+    /// Have the active setpoint start over from `from` at `now_ms`,
+    /// ramping to the final one, for when something else set the
+    /// target until then. `from` is kept within the bounds.
+    pub fn ramp_from(&mut self, from: Temperature, now_ms: u64) {
+        let centi_celsius = from.as_centi_celsius().clamp(
+            self.bounds.min.as_centi_celsius(),
+            self.bounds.max.as_centi_celsius(),
+        );
+
+        self.from = Temperature::from_centi_celsius(centi_celsius);
+        self.since_ms = now_ms;
+    }
+
+    /// The setpoint in effect at `now_ms`.
+    pub fn active(&self, now_ms: u64) -> Temperature {
+        let Some(rate) = self.rate else {
+            return self.target;
+        };
+
+        // from the start of the ramp rather than the last call,
+        // so no rounding accumulates
+        let elapsed_ms = now_ms.saturating_sub(self.since_ms);
+        let step = rate.as_centi_celsius().unsigned_abs() as u64 * elapsed_ms / 60_000;
+
+        let from = self.from.as_centi_celsius() as i64;
+        let distance = self.target.as_centi_celsius() as i64 - from;
+
+        if distance.unsigned_abs() <= step {
+            self.target
+        } else {
+            // between `from` and `target`, so in range
+            Temperature::from_centi_celsius((from + step as i64 * distance.signum()) as i16)
+        }
+    }
+
+    /// The setpoint the active one ends up at.
+    pub const fn target(&self) -> Temperature {
+        self.target
+    }
+
+    pub const fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+}
@@ -0,0 +1,142 @@
+use common::types::pump::PumpState;
+use control::cycling::{Deferral, Guard, Limits, TooManyStarts, MAX_STARTS};
+
+const LIMITS: Limits = Limits {
+    min_on_ms: 60_000,
+    min_off_ms: 30_000,
+    max_starts_per_hour: 3,
+};
+
+const MINUTE_MS: u64 = 60_000;
+
+#[test]
+fn minimum_durations() {
+    let mut guard = Guard::new(LIMITS).unwrap();
+
+    // anything goes before the state is known
+    assert_eq!(guard.check(0, PumpState::On), Ok(()));
+
+    guard.record(0, PumpState::On);
+
+    assert_eq!(guard.check(1000, PumpState::On), Ok(()));
+    assert_eq!(
+        guard.check(20_000, PumpState::Off),
+        Err(Deferral::MinOnTime {
+            remaining_ms: 40_000
+        })
+    );
+    assert_eq!(guard.check(60_000, PumpState::Off), Ok(()));
+
+    guard.record(60_000, PumpState::Off);
+
+    assert_eq!(
+        guard.check(70_000, PumpState::On),
+        Err(Deferral::MinOffTime {
+            remaining_ms: 20_000
+        })
+    );
+    assert_eq!(guard.check(90_000, PumpState::On), Ok(()));
+}
+
+#[test]
+fn repeated_records_are_no_transitions() {
+    let mut guard = Guard::new(LIMITS).unwrap();
+
+    for at_ms in (0..60_000).step_by(1000) {
+        guard.record(at_ms, PumpState::On);
+    }
+
+    assert_eq!(guard.check(60_000, PumpState::Off), Ok(()));
+}
+
+#[test]
+fn starts_per_rolling_hour() {
+    let mut guard = Guard::new(LIMITS).unwrap();
+
+    // start every 10 minutes, running for 5
+    for start in 0..3 {
+        let at_ms = start * 10 * MINUTE_MS;
+
+        assert_eq!(guard.check(at_ms, PumpState::On), Ok(()));
+        guard.record(at_ms, PumpState::On);
+        guard.record(at_ms + 5 * MINUTE_MS, PumpState::Off);
+    }
+
+    // the first start is 30 minutes ago
+    assert_eq!(
+        guard.check(30 * MINUTE_MS, PumpState::On),
+        Err(Deferral::StartLimit {
+            remaining_ms: 30 * MINUTE_MS
+        })
+    );
+
+    // and drops out of the hour
+    assert_eq!(guard.check(60 * MINUTE_MS, PumpState::On), Ok(()));
+    guard.record(60 * MINUTE_MS, PumpState::On);
+    guard.record(65 * MINUTE_MS, PumpState::Off);
+
+    // now the second one has to
+    assert_eq!(
+        guard.check(66 * MINUTE_MS, PumpState::On),
+        Err(Deferral::StartLimit {
+            remaining_ms: 4 * MINUTE_MS
+        })
+    );
+}
+
+#[test]
+fn zero_starts_is_no_limit() {
+    let mut guard = Guard::new(Limits {
+        max_starts_per_hour: 0,
+        ..LIMITS
+    })
+    .unwrap();
+
+    for start in 0..100 {
+        let at_ms = start * 2 * MINUTE_MS;
+
+        assert_eq!(guard.check(at_ms, PumpState::On), Ok(()));
+        guard.record(at_ms, PumpState::On);
+        guard.record(at_ms + MINUTE_MS, PumpState::Off);
+    }
+}
+
+#[test]
+fn rejects_more_starts_than_remembered() {
+    let max_starts_per_hour = MAX_STARTS as u8 + 1;
+
+    assert_eq!(
+        Guard::new(Limits {
+            max_starts_per_hour,
+            ..LIMITS
+        })
+        .unwrap_err(),
+        TooManyStarts {
+            max_starts_per_hour
+        }
+    );
+
+    assert!(Guard::new(Limits {
+        max_starts_per_hour: MAX_STARTS as u8,
+        ..LIMITS
+    })
+    .is_ok());
+}
+
+#[test]
+fn approved_transitions_count_at_once() {
+    let mut guard = Guard::new(LIMITS).unwrap();
+
+    guard.record(0, PumpState::Off);
+
+    assert_eq!(guard.request(MINUTE_MS, PumpState::On), Ok(()));
+
+    // the pump has not reported back yet
+    assert_eq!(
+        guard.request(MINUTE_MS + 200, PumpState::Off),
+        Err(Deferral::MinOnTime {
+            remaining_ms: 59_800
+        })
+    );
+    assert_eq!(guard.request(2 * MINUTE_MS, PumpState::Off), Ok(()));
+}
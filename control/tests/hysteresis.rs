@@ -0,0 +1,101 @@
+use common::types::{pump::PumpState, temperature::Temperature};
+use control::hysteresis::Hysteresis;
+
+const SETPOINT: Temperature = Temperature::from_celsius(60);
+
+/// A reproducible stream of noise in hundredths of a degree,
+/// uniform in `-amplitude..=amplitude`.
+fn noise(amplitude: i16) -> impl Iterator<Item = i16> {
+    let mut state: u32 = 0x2545_f491;
+
+    core::iter::repeat_with(move || {
+        // xorshift
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+
+        (state % (2 * amplitude as u32 + 1)) as i16 - amplitude
+    })
+}
+
+/// The temperature drifting `swing` hundredths of a degree around
+/// the setpoint over `cycles` slow cycles, with noise on top.
+fn trace(swing: f32, cycles: usize, noise_amplitude: i16) -> Vec<Temperature> {
+    const PER_CYCLE: usize = 600;
+
+    (0..cycles * PER_CYCLE)
+        .zip(noise(noise_amplitude))
+        .map(|(i, noise)| {
+            let phase = i as f32 / PER_CYCLE as f32 * core::f32::consts::TAU;
+            let drift = (swing * phase.sin()) as i16;
+
+            SETPOINT.saturating_add(Temperature::from_centi_celsius(drift + noise))
+        })
+        .collect()
+}
+
+/// How often the pump switches following `trace`.
+fn transitions(hysteresis: Hysteresis, trace: &[Temperature]) -> usize {
+    let mut state = PumpState::Off;
+    let mut count = 0;
+
+    for &temperature in trace {
+        let target = hysteresis.target(SETPOINT, temperature, state);
+
+        if target != state {
+            count += 1;
+            state = target;
+        }
+    }
+
+    count
+}
+
+#[test]
+fn noise_narrower_than_the_bands_does_not_flip_the_pump() {
+    let hysteresis = Hysteresis {
+        upper: Temperature::from_centi_celsius(50),
+        lower: Temperature::from_centi_celsius(50),
+    };
+
+    // +-0.4 °C of noise within a 1 °C wide deadband
+    let trace = trace(200., 5, 40);
+
+    // on and off once per cycle, plus the initial switch
+    assert!(transitions(hysteresis, &trace) <= 2 * 5 + 1);
+}
+
+#[test]
+fn noise_flips_the_pump_without_hysteresis() {
+    let trace = trace(200., 5, 40);
+
+    assert!(transitions(Hysteresis::NONE, &trace) > 50);
+}
+
+#[test]
+fn holds_state_inside_the_bands() {
+    let hysteresis = Hysteresis {
+        upper: Temperature::from_centi_celsius(100),
+        lower: Temperature::from_centi_celsius(200),
+    };
+
+    for centi_celsius in [-200, -50, 0, 50, 100] {
+        let temperature = SETPOINT.saturating_add(Temperature::from_centi_celsius(centi_celsius));
+
+        for state in [PumpState::On, PumpState::Off] {
+            assert_eq!(hysteresis.target(SETPOINT, temperature, state), state);
+        }
+    }
+
+    let above = SETPOINT.saturating_add(Temperature::from_centi_celsius(101));
+    let below = SETPOINT.saturating_sub(Temperature::from_centi_celsius(201));
+
+    assert_eq!(
+        hysteresis.target(SETPOINT, above, PumpState::Off),
+        PumpState::On
+    );
+    assert_eq!(
+        hysteresis.target(SETPOINT, below, PumpState::On),
+        PumpState::Off
+    );
+}
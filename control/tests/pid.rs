@@ -0,0 +1,96 @@
+use control::pid::{Action, Config, Pid};
+
+const SECOND_MS: u32 = 1000;
+
+fn config(kp: f32, ki: f32, kd: f32) -> Config {
+    Config {
+        kp,
+        ki,
+        kd,
+        action: Action::Direct,
+        output_min: 0.,
+        output_max: 100.,
+    }
+}
+
+#[test]
+fn output_is_clamped() {
+    let mut pid = Pid::new(config(10., 0., 0.));
+
+    assert_eq!(pid.update(60., 0., SECOND_MS), 100.);
+    assert_eq!(pid.update(0., 60., SECOND_MS), 0.);
+}
+
+#[test]
+fn reverse_action() {
+    let mut pid = Pid::new(Config {
+        action: Action::Reverse,
+        ..config(1., 0., 0.)
+    });
+
+    // too hot, cool harder
+    assert_eq!(pid.update(60., 70., SECOND_MS), 10.);
+    assert_eq!(pid.update(60., 50., SECOND_MS), 0.);
+}
+
+#[test]
+fn integral_holds_while_saturated() {
+    let mut pid = Pid::new(config(1., 1., 0.));
+
+    // the integral takes the output to the limit and stops there
+    assert_eq!(pid.update(50., 0., SECOND_MS), 100.);
+
+    for _ in 0..100 {
+        assert_eq!(pid.update(50., 0., SECOND_MS), 100.);
+    }
+
+    // so it unwinds at once when the error flips,
+    // rather than after 100 seconds of accumulation
+    assert_eq!(pid.update(0., 10., SECOND_MS), 30.);
+    assert_eq!(pid.update(0., 10., SECOND_MS), 20.);
+    assert_eq!(pid.update(0., 10., SECOND_MS), 10.);
+    assert_eq!(pid.update(0., 10., SECOND_MS), 0.);
+}
+
+#[test]
+fn setpoint_step_does_not_kick_derivative() {
+    let mut pid = Pid::new(Config {
+        output_max: 1000.,
+        ..config(1., 0., 10.)
+    });
+
+    assert_eq!(pid.update(50., 40., SECOND_MS), 10.);
+
+    // only the proportional term follows the setpoint
+    assert_eq!(pid.update(60., 40., SECOND_MS), 20.);
+
+    // while the derivative damps the measurement
+    assert_eq!(pid.update(60., 41., SECOND_MS), 9.);
+}
+
+#[test]
+fn zero_dt_first_update() {
+    let mut pid = Pid::new(config(1., 100., 100.));
+
+    // no time passed, so only the proportional term acts
+    assert_eq!(pid.update(50., 40., 0), 10.);
+
+    // and a repeated timestamp does not divide by zero
+    let output = pid.update(50., 45., 0);
+
+    assert!(output.is_finite());
+    assert_eq!(output, 5.);
+}
+
+#[test]
+fn reset_forgets_history() {
+    let mut pid = Pid::new(config(1., 1., 10.));
+
+    pid.update(50., 40., SECOND_MS);
+    pid.update(50., 40., SECOND_MS);
+
+    pid.reset();
+
+    // neither integral nor derivative carry over
+    assert_eq!(pid.update(50., 45., SECOND_MS), 10.);
+}
@@ -0,0 +1,86 @@
+use common::types::temperature::Temperature;
+use control::schedule::{Schedule, Segment, Status};
+
+const MINUTE_MS: u64 = 60_000;
+
+/// Hold 40 °C for 10 minutes, ramp to 60 °C over 5 minutes, hold.
+const PROFILE: &[Segment] = &[
+    Segment::Hold {
+        temperature: Temperature::from_celsius(40),
+        duration_ms: 10 * 60_000,
+    },
+    Segment::Ramp {
+        temperature: Temperature::from_celsius(60),
+        duration_ms: 5 * 60_000,
+    },
+    Segment::Hold {
+        temperature: Temperature::from_celsius(60),
+        duration_ms: 30 * 60_000,
+    },
+];
+
+#[test]
+fn follows_the_profile() {
+    let mut schedule = Schedule::IDLE;
+
+    assert_eq!(schedule.progress(0), None);
+
+    schedule.start(PROFILE, Temperature::from_celsius(25), MINUTE_MS);
+
+    let progress = schedule.progress(5 * MINUTE_MS).unwrap();
+    assert_eq!(progress.status, Status::Running);
+    assert_eq!(progress.index, 0);
+    assert_eq!(progress.target, Temperature::from_celsius(40));
+    assert_eq!(progress.remaining_ms, 6 * MINUTE_MS);
+    assert_eq!(progress.total_remaining_ms, 41 * MINUTE_MS);
+
+    // halfway up the ramp
+    let progress = schedule.progress(13 * MINUTE_MS + MINUTE_MS / 2).unwrap();
+    assert_eq!(progress.index, 1);
+    assert_eq!(progress.target, Temperature::from_celsius(50));
+    assert_eq!(progress.remaining_ms, 5 * MINUTE_MS / 2);
+
+    let progress = schedule.progress(16 * MINUTE_MS).unwrap();
+    assert_eq!(progress.index, 2);
+    assert_eq!(progress.target, Temperature::from_celsius(60));
+
+    // holds the last temperature once done
+    let progress = schedule.progress(100 * MINUTE_MS).unwrap();
+    assert_eq!(progress.status, Status::Finished);
+    assert_eq!(progress.index, 2);
+    assert_eq!(progress.target, Temperature::from_celsius(60));
+    assert_eq!(progress.total_remaining_ms, 0);
+}
+
+#[test]
+fn pause_freezes_the_profile() {
+    let mut schedule = Schedule::IDLE;
+
+    schedule.start(PROFILE, Temperature::from_celsius(25), 0);
+    schedule.pause(12 * MINUTE_MS);
+
+    let paused = schedule.progress(12 * MINUTE_MS).unwrap();
+    assert_eq!(paused.status, Status::Paused);
+    assert_eq!(paused.target, Temperature::from_celsius(48));
+
+    // nothing moves while paused
+    let later = schedule.progress(20 * MINUTE_MS).unwrap();
+    assert_eq!(later, paused);
+
+    schedule.resume(20 * MINUTE_MS);
+
+    let progress = schedule.progress(21 * MINUTE_MS).unwrap();
+    assert_eq!(progress.status, Status::Running);
+    assert_eq!(progress.target, Temperature::from_celsius(52));
+}
+
+#[test]
+fn abort_stops_the_profile() {
+    let mut schedule = Schedule::IDLE;
+
+    schedule.start(PROFILE, Temperature::from_celsius(25), 0);
+    schedule.abort();
+
+    assert_eq!(schedule.progress(MINUTE_MS), None);
+    assert_eq!(schedule.target(MINUTE_MS), None);
+}
@@ -0,0 +1,113 @@
+use common::types::temperature::Temperature;
+use control::setpoint::{Bounds, Invalid, OutOfBounds, Setpoint};
+
+const BOUNDS: Bounds = Bounds {
+    min: Temperature::from_celsius(20),
+    max: Temperature::from_celsius(80),
+};
+
+const MINUTE_MS: u64 = 60_000;
+
+#[test]
+fn rejects_setpoints_out_of_bounds() {
+    let requested = Temperature::from_celsius(90);
+
+    assert_eq!(
+        Setpoint::new(requested, BOUNDS, None).unwrap_err(),
+        Invalid::OutOfBounds(OutOfBounds {
+            requested,
+            bounds: BOUNDS
+        })
+    );
+
+    let mut setpoint = Setpoint::new(Temperature::from_celsius(60), BOUNDS, None).unwrap();
+
+    assert!(setpoint.set(Temperature::from_celsius(19), 0).is_err());
+    assert!(setpoint.set(BOUNDS.min, 0).is_ok());
+    assert!(setpoint.set(BOUNDS.max, 0).is_ok());
+}
+
+#[test]
+fn jumps_without_rate() {
+    let mut setpoint = Setpoint::new(Temperature::from_celsius(60), BOUNDS, None).unwrap();
+
+    setpoint.set(Temperature::from_celsius(40), 1000).unwrap();
+
+    assert_eq!(setpoint.active(1000), Temperature::from_celsius(40));
+}
+
+#[test]
+fn ramps_at_rate() {
+    // 2 °C per minute
+    let rate = Some(Temperature::from_celsius(2));
+    let mut setpoint = Setpoint::new(Temperature::from_celsius(60), BOUNDS, rate).unwrap();
+
+    setpoint.set(Temperature::from_celsius(50), 0).unwrap();
+
+    assert_eq!(setpoint.target(), Temperature::from_celsius(50));
+    assert_eq!(setpoint.active(0), Temperature::from_celsius(60));
+    assert_eq!(
+        setpoint.active(MINUTE_MS / 2),
+        Temperature::from_celsius(59)
+    );
+    assert_eq!(
+        setpoint.active(2 * MINUTE_MS),
+        Temperature::from_celsius(56)
+    );
+    assert_eq!(
+        setpoint.active(10 * MINUTE_MS),
+        Temperature::from_celsius(50)
+    );
+
+    // turning around mid ramp starts from where it got to
+    setpoint
+        .set(Temperature::from_celsius(70), 2 * MINUTE_MS)
+        .unwrap();
+
+    assert_eq!(
+        setpoint.active(3 * MINUTE_MS),
+        Temperature::from_celsius(58)
+    );
+    assert_eq!(
+        setpoint.active(20 * MINUTE_MS),
+        Temperature::from_celsius(70)
+    );
+}
+
+#[test]
+fn rejects_rates_not_above_zero() {
+    let target = Temperature::from_celsius(60);
+
+    for rate in [Temperature::ZERO, Temperature::from_celsius(-2)] {
+        assert_eq!(
+            Setpoint::new(target, BOUNDS, Some(rate)).unwrap_err(),
+            Invalid::Rate(rate)
+        );
+    }
+
+    assert!(Setpoint::new(target, BOUNDS, Some(Temperature::from_centi_celsius(1))).is_ok());
+}
+
+#[test]
+fn ramps_from_where_it_takes_over() {
+    let rate = Some(Temperature::from_celsius(2));
+    let mut setpoint = Setpoint::new(Temperature::from_celsius(60), BOUNDS, rate).unwrap();
+
+    setpoint.ramp_from(Temperature::from_celsius(50), MINUTE_MS);
+
+    assert_eq!(setpoint.target(), Temperature::from_celsius(60));
+    assert_eq!(setpoint.active(MINUTE_MS), Temperature::from_celsius(50));
+    assert_eq!(
+        setpoint.active(3 * MINUTE_MS),
+        Temperature::from_celsius(54)
+    );
+    assert_eq!(
+        setpoint.active(10 * MINUTE_MS),
+        Temperature::from_celsius(60)
+    );
+
+    // never from outside the bounds
+    setpoint.ramp_from(Temperature::from_celsius(95), 0);
+
+    assert_eq!(setpoint.active(0), BOUNDS.max);
+}
@@ -4,12 +4,23 @@
 mod fmt;
 
 use common::{
-    command::{self, pump::Fault},
-    types::{pump::PumpState, temperature::Temperature},
+    command::{
+        self,
+        description::{Direction, Message},
+        pump::Fault,
+        Address, NackReason, TransactionId, POINT_TO_POINT,
+    },
+    frame,
+    types::{
+        device::{Identity, Kind, Version},
+        link::{self, BaudRate},
+        pump::{Failsafe, PumpState, Speed, Telemetry},
+        temperature::{Calibration, Temperature},
+    },
 };
 use cookie_cutter::SerializeIter;
 use embassy_sync::{blocking_mutex::raw::ThreadModeRawMutex, mutex::Mutex};
-use embassy_time::Timer;
+use embassy_time::{with_deadline, Duration, Instant, Timer};
 use embedded_command::command_buffer::CommandBuffer;
 
 #[cfg(not(feature = "defmt"))]
@@ -20,7 +31,7 @@ use {defmt_rtt as _, panic_probe as _};
 use embassy_executor::Spawner;
 use embassy_stm32::{
     bind_interrupts, mode, peripherals,
-    usart::{self, Uart},
+    usart::{self, Uart, UartRx, UartTx},
 };
 
 bind_interrupts!(struct Irqs {
@@ -28,137 +39,643 @@ bind_interrupts!(struct Irqs {
     USART2 => usart::InterruptHandler<peripherals::USART2>;
 });
 
-type FakeFault = bool;
+const FIRMWARE: Version = Version {
+    major: 0,
+    minor: 1,
+    patch: 0,
+};
+
+/// Sensors sharing the temperature bus and how
+/// far each reads from the process temperature.
+const TEMP_SENSORS: [(Address, Temperature); 3] = [
+    (0x10, Temperature::ZERO),
+    (0x11, Temperature::from_centi_celsius(-40)),
+    (0x12, Temperature::from_centi_celsius(25)),
+];
+
+/// Shortest period measurements are pushed at.
+const MIN_STREAM_PERIOD_MS: u16 = 100;
+
+/// The pump is alone on its bus.
+const PUMP_ADDRESS: Address = 0x20;
+
+/// The fault condition currently being faked, if any.
+type FakeFault = Option<Fault>;
+
+/// Heat picked up by the process every simulation step.
+const HEATING: Temperature = Temperature::from_centi_celsius(75);
+
+/// Heat carried away every simulation step by the pump at full speed.
+const COOLING: Temperature = Temperature::from_centi_celsius(150);
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct State {
+    temperature: Temperature,
+    pump_state: PumpState,
+    pump_speed: Speed,
+    /// The latched fault, if any.
+    fault: Option<Fault>,
+    fake_fault: FakeFault,
+    sensor_fault: Option<command::temperature::Fault>,
+    /// One per entry of [`TEMP_SENSORS`].
+    calibrations: [Calibration; TEMP_SENSORS.len()],
+    failsafe: Failsafe,
+    last_heartbeat: Instant,
+}
+
+static STATE: Mutex<ThreadModeRawMutex, State> = Mutex::new(State {
+    temperature: Temperature::from_celsius(25),
+    pump_state: PumpState::Off,
+    pump_speed: Speed::FULL,
+    fault: None,
+    fake_fault: None,
+    // the sensor takes a moment to warm up
+    sensor_fault: Some(command::temperature::Fault::NotReady),
+    calibrations: [Calibration::IDENTITY; TEMP_SENSORS.len()],
+    failsafe: Failsafe::DISABLED,
+    last_heartbeat: Instant::from_ticks(0),
+});
+
+/// The index into [`TEMP_SENSORS`] of the sensor
+/// `address` is for, if any.
+fn temp_sensor(address: Address) -> Option<usize> {
+    // every sensor would answer a point-to-point request at
+    // once and garble the bus, the first one stands in for
+    // a sensor alone on its bus
+    if address == POINT_TO_POINT {
+        return Some(0);
+    }
+
+    TEMP_SENSORS.iter().position(|(a, _)| *a == address)
+}
+
+/// A sensor pushing measurements.
+#[derive(Clone, Copy)]
+struct Subscription {
+    id: TransactionId,
+    period: Duration,
+    next: Instant,
+}
+
+/// One slot per entry of [`TEMP_SENSORS`].
+static SUBSCRIPTIONS: Mutex<ThreadModeRawMutex, [Option<Subscription>; TEMP_SENSORS.len()]> =
+    Mutex::new([None; TEMP_SENSORS.len()]);
+
+/// Shared by the request handler and the stream.
+static TEMP_TX: Mutex<ThreadModeRawMutex, Option<UartTx<'static, mode::Async>>> = Mutex::new(None);
+
+impl State {
+    /// What the sensor at `index` of [`TEMP_SENSORS`] shows.
+    fn reading(&self, index: usize) -> command::temperature::FromPeripheral {
+        use command::temperature::FromPeripheral;
+
+        let (_, offset) = TEMP_SENSORS[index];
+
+        match self.sensor_fault {
+            Some(fault) => FromPeripheral::Fault(fault),
+            None => FromPeripheral::Temperature(
+                self.calibrations[index].apply(self.temperature.saturating_add(offset)),
+            ),
+        }
+    }
+
+    /// Readings of a small centrifugal pump at the current speed.
+    fn telemetry(&self) -> Telemetry {
+        let percent = match self.pump_state {
+            PumpState::On => self.pump_speed.as_percent() as u16,
+            PumpState::Off => 0,
+        };
+
+        // a little ripple on the current keeps it looking alive
+        let ripple = (self.temperature.as_centi_celsius() as u16) % 16;
+
+        Telemetry {
+            current_ma: if percent == 0 {
+                0
+            } else {
+                150 + percent * 18 + ripple
+            },
+            rpm: percent * 30,
+            flow_ml_min: percent * 120,
+        }
+    }
+}
+
+/// Make out who a request which failed to deserialize
+/// was for, and why it is refused.
+fn nack(messages: &[Message], payload: &[u8]) -> Option<(Address, TransactionId, NackReason)> {
+    let [address, id, rest @ ..] = payload else {
+        return None;
+    };
+
+    let known = |opcode| {
+        messages
+            .iter()
+            .any(|message| message.direction == Direction::ToPeripheral && message.opcode == opcode)
+    };
+
+    let reason = match rest.first() {
+        Some(&opcode) if !known(opcode) => NackReason::UnknownOpcode,
+        _ => NackReason::BadPayload,
+    };
+
+    Some((*address, *id, reason))
+}
+
+/// When a link which just switched rates falls back
+/// to the default unless a request confirms the new one.
+fn confirm_deadline() -> Instant {
+    Instant::now() + Duration::from_millis(link::CONFIRM_TIMEOUT_MS as u64)
+}
+
+/// Switch the temperature bus to `rate`.
+async fn set_temp_baud(rate: BaudRate) {
+    // holding the transmitter keeps the stream
+    // from pushing while the rate changes
+    let mut tx = TEMP_TX.lock().await;
+    let tx = fmt::unwrap!(tx.as_mut());
+
+    // the halves share one rate, so setting it on either does
+    fmt::unwrap!(tx.blocking_flush());
+    fmt::unwrap!(tx.set_baudrate(rate.as_bps()));
+}
+
+async fn send_temp(response: &command::temperature::Response) {
+    let mut buf = [0; frame::MAX_FRAME];
+
+    let n = fmt::unwrap!(frame::encode(response, &mut buf));
+    fmt::debug!("{}", buf[..n]);
 
-static STATE: Mutex<ThreadModeRawMutex, (Temperature, PumpState, FakeFault)> =
-    Mutex::new((25, PumpState::Off, false));
+    let mut tx = TEMP_TX.lock().await;
+    fmt::unwrap!(fmt::unwrap!(tx.as_mut()).write(&buf[..n]).await);
+}
 
 #[embassy_executor::task]
-async fn temp(mut uart: Uart<'static, mode::Async>) {
-    use command::temperature::{FromPeripheral, ToPeripheral};
+async fn temp(mut uart: UartRx<'static, mode::Async>) {
+    use command::temperature::{FromPeripheral, Request, Response, ToPeripheral};
 
     let mut cmd_buf = CommandBuffer::<256>::new();
+    let mut confirm_by = None;
 
     loop {
         let mut buf = [0; 1];
-        let n = fmt::unwrap!(uart.read_until_idle(&mut buf).await);
+
+        let read = uart.read_until_idle(&mut buf);
+        let result = match confirm_by {
+            Some(deadline) => with_deadline(deadline, read).await,
+            None => Ok(read.await),
+        };
+
+        let n = match result {
+            Ok(Ok(n)) => n,
+            // noise, or the controller talking at another rate
+            Ok(Err(e)) => {
+                fmt::warn!("receive error: {}", e);
+                continue;
+            }
+            Err(_) => {
+                fmt::warn!("baud rate not confirmed, falling back");
+
+                set_temp_baud(BaudRate::DEFAULT).await;
+                cmd_buf = CommandBuffer::new();
+                confirm_by = None;
+                continue;
+            }
+        };
+
         fmt::debug!("{}", buf[..n]);
         fmt::unwrap!(cmd_buf.ingest(buf[..n].iter()));
 
         let mut iter = cmd_buf.iter();
 
-        let result = ToPeripheral::deserialize_iter(&mut iter);
+        let mut payload = [0; frame::MAX_PAYLOAD];
 
-        let ToPeripheral::Read = match result {
+        let len = match frame::decode_payload(&mut iter, &mut payload) {
             // special case
-            Err(cookie_cutter::error::Error::EndOfInput) => continue,
+            Err(frame::Error::EndOfInput) => continue,
+            Err(frame::Error::Corrupt | frame::Error::Deserialize(_)) => {
+                // drop the frame and resync on the next delimiter
+                let memento = iter.capture();
+                cmd_buf.flush(memento);
+
+                fmt::warn!("dropped corrupt frame");
+                continue;
+            }
 
-            Ok(cmd) => cmd,
-            Err(e) => fmt::panic!("{}", e),
+            Ok(len) => len,
         };
 
         let memento = iter.capture();
         cmd_buf.flush(memento);
 
-        fmt::info!("received read.");
+        let Request {
+            address,
+            id,
+            command: cmd,
+        } = match Request::deserialize_iter(&payload[..len]) {
+            Ok(request) => request,
+            Err(_) => {
+                let Some((address, id, reason)) =
+                    nack(command::temperature::MESSAGES, &payload[..len])
+                else {
+                    fmt::warn!("dropped runt frame");
+                    continue;
+                };
+
+                if let Some(index) = temp_sensor(address) {
+                    fmt::warn!("refused request: {}", reason);
+
+                    send_temp(&Response {
+                        address: TEMP_SENSORS[index].0,
+                        id,
+                        command: FromPeripheral::Nack { reason },
+                    })
+                    .await;
+                }
 
-        let mut buf = [0; 8];
-        let mut n = 0;
+                continue;
+            }
+        };
 
-        let temp = {
-            let lock = STATE.lock().await;
-            lock.0
+        let Some(index) = temp_sensor(address) else {
+            fmt::trace!("ignored request for {}", address);
+            continue;
         };
 
-        fmt::unwrap!(
-            FromPeripheral::Temperature(temp).serialize_iter(buf.iter_mut().inspect(|_| {
-                n += 1;
-            }))
-        );
-        fmt::debug!("{}", buf[..n]);
-        fmt::unwrap!(uart.write(&buf[..n]).await);
+        // answer with the address of the sensor, as the pump does
+        let address = TEMP_SENSORS[index].0;
+
+        // any request at the new rate confirms it
+        confirm_by = None;
 
-        fmt::info!("sent temperature");
+        let command = match cmd {
+            ToPeripheral::Read => {
+                fmt::info!("received read.");
+
+                STATE.lock().await.reading(index)
+            }
+            ToPeripheral::Identify => {
+                fmt::info!("received identify.");
+
+                FromPeripheral::Identity(Identity {
+                    kind: Kind::Temperature,
+                    protocol: command::temperature::PROTOCOL_VERSION,
+                    firmware: FIRMWARE,
+                })
+            }
+            ToPeripheral::Subscribe { period_ms } => 'subscribe: {
+                let period_ms = period_ms.max(MIN_STREAM_PERIOD_MS);
+                fmt::info!("received subscribe, period: {} ms.", period_ms);
+
+                // nothing to push before the first conversion
+                if let Some(command::temperature::Fault::NotReady) = STATE.lock().await.sensor_fault
+                {
+                    break 'subscribe FromPeripheral::Nack {
+                        reason: NackReason::NotReady,
+                    };
+                }
+
+                let period = Duration::from_millis(period_ms as u64);
+
+                SUBSCRIPTIONS.lock().await[index] = Some(Subscription {
+                    id,
+                    period,
+                    next: Instant::now() + period,
+                });
+
+                FromPeripheral::Subscribed { period_ms }
+            }
+            ToPeripheral::Unsubscribe => {
+                fmt::info!("received unsubscribe.");
+
+                SUBSCRIPTIONS.lock().await[index] = None;
+
+                FromPeripheral::Unsubscribed
+            }
+            ToPeripheral::SetCalibration(calibration) => {
+                fmt::info!("received set calibration.");
+
+                STATE.lock().await.calibrations[index] = calibration;
+
+                FromPeripheral::Calibration(calibration)
+            }
+            ToPeripheral::GetCalibration => {
+                fmt::info!("received get calibration.");
+
+                FromPeripheral::Calibration(STATE.lock().await.calibrations[index])
+            }
+            ToPeripheral::SetBaud(rate) => {
+                fmt::info!("received set baud, {} bps.", rate.as_bps());
+
+                FromPeripheral::Baud(rate)
+            }
+        };
+
+        // hold the dummy to the protocol too
+        debug_assert!(cmd.answered_by(&command));
+
+        send_temp(&Response {
+            address,
+            id,
+            command,
+        })
+        .await;
+
+        fmt::info!("sent response");
+
+        // the simulated sensors share one transceiver,
+        // so the whole bus switches, as it has to
+        if let ToPeripheral::SetBaud(rate) = cmd {
+            set_temp_baud(rate).await;
+            confirm_by = Some(confirm_deadline());
+        }
+    }
+}
+
+#[embassy_executor::task]
+async fn temp_stream() {
+    use command::temperature::Response;
+
+    loop {
+        Timer::after_millis(10).await;
+
+        for (index, &(address, _)) in TEMP_SENSORS.iter().enumerate() {
+            let id = {
+                let mut subscriptions = SUBSCRIPTIONS.lock().await;
+
+                let Some(subscription) = subscriptions[index].as_mut() else {
+                    continue;
+                };
+
+                if Instant::now() < subscription.next {
+                    continue;
+                }
+
+                subscription.next += subscription.period;
+                subscription.id
+            };
+
+            let command = STATE.lock().await.reading(index);
+
+            // pushed measurements echo the subscribe request
+            send_temp(&Response {
+                address,
+                id,
+                command,
+            })
+            .await;
+
+            fmt::trace!("pushed measurement");
+        }
     }
 }
 
 #[embassy_executor::task]
 async fn pump(mut uart: Uart<'static, mode::Async>) {
-    use command::pump::{FromPeripheral, ToPeripheral};
+    use command::pump::{FromPeripheral, Request, Response, ToPeripheral};
 
     let mut cmd_buf = CommandBuffer::<256>::new();
+    let mut confirm_by = None;
 
     loop {
         let mut buf = [0; 1];
-        let n = fmt::unwrap!(uart.read_until_idle(&mut buf).await);
+
+        let read = uart.read_until_idle(&mut buf);
+        let result = match confirm_by {
+            Some(deadline) => with_deadline(deadline, read).await,
+            None => Ok(read.await),
+        };
+
+        let n = match result {
+            Ok(Ok(n)) => n,
+            // noise, or the controller talking at another rate
+            Ok(Err(e)) => {
+                fmt::warn!("receive error: {}", e);
+                continue;
+            }
+            Err(_) => {
+                fmt::warn!("baud rate not confirmed, falling back");
+
+                fmt::unwrap!(uart.set_baudrate(BaudRate::DEFAULT.as_bps()));
+                cmd_buf = CommandBuffer::new();
+                confirm_by = None;
+                continue;
+            }
+        };
+
         fmt::trace!("{}", buf[..n]);
         fmt::unwrap!(cmd_buf.ingest(buf[..n].iter()));
 
         let mut iter = cmd_buf.iter();
 
-        let result = ToPeripheral::deserialize_iter(&mut iter);
+        let mut payload = [0; frame::MAX_PAYLOAD];
 
-        let cmd = match result {
+        let len = match frame::decode_payload(&mut iter, &mut payload) {
             // special case
-            Err(cookie_cutter::error::Error::EndOfInput) => continue,
+            Err(frame::Error::EndOfInput) => continue,
+            Err(frame::Error::Corrupt | frame::Error::Deserialize(_)) => {
+                // drop the frame and resync on the next delimiter
+                let memento = iter.capture();
+                cmd_buf.flush(memento);
+
+                fmt::warn!("dropped corrupt frame");
+                continue;
+            }
 
-            Ok(cmd) => cmd,
-            Err(e) => fmt::panic!("{}", e),
+            Ok(len) => len,
         };
 
         let memento = iter.capture();
         cmd_buf.flush(memento);
 
+        let Request {
+            address,
+            id,
+            command: cmd,
+        } = match Request::deserialize_iter(&payload[..len]) {
+            Ok(request) => request,
+            Err(_) => {
+                let Some((address, id, reason)) = nack(command::pump::MESSAGES, &payload[..len])
+                else {
+                    fmt::warn!("dropped runt frame");
+                    continue;
+                };
+
+                if address == PUMP_ADDRESS || address == POINT_TO_POINT {
+                    fmt::warn!("refused request: {}", reason);
+
+                    let mut buf = [0; frame::MAX_FRAME];
+
+                    let outgoing = Response {
+                        address: PUMP_ADDRESS,
+                        id,
+                        command: FromPeripheral::Nack { reason },
+                    };
+
+                    let n = fmt::unwrap!(frame::encode(&outgoing, &mut buf));
+                    fmt::unwrap!(uart.write(&buf[..n]).await);
+                }
+
+                continue;
+            }
+        };
+
+        if address != PUMP_ADDRESS && address != POINT_TO_POINT {
+            fmt::trace!("ignored request for {}", address);
+            continue;
+        }
+
         fmt::trace!("received cmd: {}.", cmd);
 
-        let mut buf = [0; 8];
-        let mut n = 0;
+        // any request at the new rate confirms it
+        confirm_by = None;
+
+        let mut buf = [0; frame::MAX_FRAME];
 
         let outgoing = {
             let mut state = STATE.lock().await;
 
-            if state.2 {
-                FromPeripheral::Fault(Fault::Current)
-            } else {
-                match cmd {
-                    ToPeripheral::Get => FromPeripheral::PumpState(state.1),
-                    ToPeripheral::Set(new_state) => {
-                        state.1 = new_state;
+            // any request keeps the failsafe from tripping, not only heartbeats
+            state.last_heartbeat = Instant::now();
+
+            let command = match (cmd, state.fault) {
+                (ToPeripheral::Identify, _) => FromPeripheral::Identity(Identity {
+                    kind: Kind::Pump,
+                    protocol: command::pump::PROTOCOL_VERSION,
+                    firmware: FIRMWARE,
+                }),
+                (ToPeripheral::ClearFault, _) => {
+                    // the fault only unlatches once its cause is gone
+                    if state.fake_fault.is_none() {
+                        state.fault = None;
+                    }
 
-                        FromPeripheral::PumpState(state.1)
+                    match state.fault {
+                        Some(fault) => FromPeripheral::Fault(fault),
+                        None => FromPeripheral::PumpState(state.pump_state),
                     }
                 }
+                (ToPeripheral::ConfigureFailsafe(failsafe), _) => {
+                    // the timeout runs from arming, not from boot,
+                    // as the request restarted it above
+                    state.failsafe = failsafe;
+
+                    FromPeripheral::Failsafe(state.failsafe)
+                }
+                (ToPeripheral::SetBaud(rate), _) => FromPeripheral::Baud(rate),
+                // a latched fault answers everything else
+                (_, Some(fault)) => FromPeripheral::Fault(fault),
+                (ToPeripheral::Get | ToPeripheral::Heartbeat, _) => {
+                    FromPeripheral::PumpState(state.pump_state)
+                }
+                (ToPeripheral::Set(new_state), _) => {
+                    state.pump_state = new_state;
+
+                    FromPeripheral::PumpState(state.pump_state)
+                }
+                (ToPeripheral::SetSpeed(new_speed), _) if !new_speed.is_valid() => {
+                    fmt::warn!("refused speed: {}", new_speed);
+
+                    FromPeripheral::Nack {
+                        reason: NackReason::BadPayload,
+                    }
+                }
+                (ToPeripheral::SetSpeed(new_speed), _) => {
+                    state.pump_speed = new_speed;
+
+                    FromPeripheral::Speed(state.pump_speed)
+                }
+                (ToPeripheral::GetTelemetry, _) => FromPeripheral::Telemetry(state.telemetry()),
+            };
+
+            // hold the dummy to the protocol too
+            debug_assert!(cmd.answered_by(&command));
+
+            // echo the transaction ID
+            Response {
+                address: PUMP_ADDRESS,
+                id,
+                command,
             }
         };
 
-        fmt::unwrap!(outgoing.serialize_iter(buf.iter_mut().inspect(|_| {
-            n += 1;
-        })));
+        let n = fmt::unwrap!(frame::encode(&outgoing, &mut buf));
         fmt::debug!("{}", buf[..n]);
         fmt::unwrap!(uart.write(&buf[..n]).await);
 
-        fmt::trace!("sent pump state");
+        fmt::trace!("sent response");
+
+        if let ToPeripheral::SetBaud(rate) = cmd {
+            // the answer goes out at the old rate
+            fmt::unwrap!(uart.blocking_flush());
+            fmt::unwrap!(uart.set_baudrate(rate.as_bps()));
+
+            confirm_by = Some(confirm_deadline());
+        }
     }
 }
 
 #[embassy_executor::task]
 async fn simulator() {
-    for _ in 0..50 {
+    let mut step: u32 = 0;
+
+    loop {
         {
             let mut state = STATE.lock().await;
 
             fmt::info!("state: {}", *state);
 
-            match state.1 {
-                PumpState::Off => state.0 += 1,
-                PumpState::On => state.0 -= 1,
+            state.temperature = state.temperature.saturating_add(HEATING);
+
+            if let PumpState::On = state.pump_state {
+                // cooling is proportional to speed
+                let cooling = COOLING.as_centi_celsius() as i32
+                    * state.pump_speed.as_percent() as i32
+                    / Speed::FULL.as_percent() as i32;
+
+                state.temperature = state
+                    .temperature
+                    .saturating_sub(Temperature::from_centi_celsius(cooling as i16));
+            }
+
+            if step == 4 {
+                state.sensor_fault = None;
+            }
+
+            // fake a short over-current every so often
+            match step % 100 {
+                50 => state.fake_fault = Some(Fault::Current),
+                54 => state.fake_fault = None,
+                _ => {}
+            }
+
+            // the controller went quiet, a latched
+            // fault keeps the pump off regardless
+            let Failsafe {
+                timeout_ms,
+                safe_state,
+            } = state.failsafe;
+
+            if timeout_ms != 0
+                && state.fault.is_none()
+                && state.last_heartbeat.elapsed() > Duration::from_millis(timeout_ms as u64)
+                && state.pump_state != safe_state
+            {
+                fmt::warn!("failsafe tripped, pump is {}", safe_state);
+                state.pump_state = safe_state;
+            }
+
+            // faults latch and stop the pump
+            if let (Some(fault), None) = (state.fake_fault, state.fault) {
+                state.fault = Some(fault);
+                state.pump_state = PumpState::Off;
             }
         }
 
+        step = step.wrapping_add(1);
+
         Timer::after_millis(500).await;
     }
-
-    STATE.lock().await.2 = true;
 }
 
 #[embassy_executor::main]
@@ -166,7 +683,7 @@ async fn main(spawner: Spawner) {
     let p = embassy_stm32::init(Default::default());
 
     let mut usart_cfg = usart::Config::default();
-    usart_cfg.baudrate = 9600;
+    usart_cfg.baudrate = BaudRate::DEFAULT.as_bps();
 
     let usart1 = fmt::unwrap!(Uart::new(
         p.USART1, p.PA10, p.PA9, Irqs, p.DMA1_CH1, p.DMA1_CH2, usart_cfg,
@@ -176,7 +693,11 @@ async fn main(spawner: Spawner) {
         p.USART2, p.PB4, p.PB3, Irqs, p.DMA1_CH3, p.DMA1_CH4, usart_cfg,
     ));
 
-    spawner.must_spawn(temp(usart1));
+    let (tx1, rx1) = usart1.split();
+    *TEMP_TX.lock().await = Some(tx1);
+
+    spawner.must_spawn(temp(rx1));
+    spawner.must_spawn(temp_stream());
     spawner.must_spawn(pump(usart2));
     spawner.must_spawn(simulator());
 }
@@ -14,13 +14,28 @@ use {defmt_rtt as _, panic_probe as _};
 mod app {
     use crate::{
         model::Model,
-        peripherals::{pump::Pump, temperature::TempSensor},
+        peripherals::{link::LineRate, pump::Pump, temperature::TempSensor},
+    };
+    use common::{
+        command::Address,
+        types::{
+            link::BaudRate,
+            temperature::{Calibration, Gain, Temperature},
+        },
+    };
+
+    use control::{
+        cycling::{Guard, Limits},
+        hysteresis::Hysteresis,
+        schedule::Segment,
+        setpoint::{Bounds, Setpoint},
+        Control,
     };
 
     use super::fmt;
 
     // monotonics
-    use rtic_monotonics::{fugit::ExtU64 as _, stm32_tim2_monotonic, Monotonic as _};
+    use rtic_monotonics::{fugit::ExtU64, stm32_tim2_monotonic, Monotonic as _};
     const MONO_FREQ: u32 = 31_250;
     stm32_tim2_monotonic!(Mono, MONO_FREQ);
 
@@ -34,6 +49,82 @@ mod app {
     };
 
     // const configs
+    const TEMP_SENSOR_ADDRESS: Address = 0x10;
+    const PUMP_ADDRESS: Address = 0x20;
+
+    const TARGET_TEMP: Temperature = Temperature::from_celsius(60);
+
+    /// What the process tolerates, setpoints outside are refused.
+    const TARGET_TEMP_BOUNDS: Bounds = Bounds {
+        min: Temperature::from_celsius(20),
+        max: Temperature::from_celsius(80),
+    };
+
+    /// How fast setpoint changes take effect, per minute.
+    const TARGET_TEMP_RATE: Option<Temperature> = Some(Temperature::from_celsius(2));
+
+    /// Run from boot, the setpoint takes over
+    /// at its rate once the profile is done.
+    const PROFILE: &[Segment] = &[
+        Segment::Hold {
+            temperature: Temperature::from_celsius(40),
+            duration_ms: 600_000,
+        },
+        Segment::Ramp {
+            temperature: TARGET_TEMP,
+            duration_ms: 300_000,
+        },
+    ];
+
+    /// How often the target temperature, pump
+    /// telemetry and schedule are reported.
+    const STATUS_PERIOD_MS: u64 = 10_000;
+
+    /// Full speed above the target, off below, switching only half
+    /// a degree either side so sensor noise cannot flip it.
+    ///
+    /// On/off control until PID gains are tuned on the process, then
+    /// for example
+    ///
+    /// ```ignore
+    /// Control::Pid {
+    ///     // full output once the process is 10 °C too hot
+    ///     pid: Pid::new(pid::Config {
+    ///         kp: 10.,
+    ///         ki: 0.1,
+    ///         kd: 0.,
+    ///         action: Action::Reverse,
+    ///         output_min: 0.,
+    ///         output_max: 100.,
+    ///     }),
+    ///     drive: Drive::Speed,
+    /// }
+    /// ```
+    ///
+    /// for pump speed to follow the PID output. A pump without speed
+    /// control takes the output as its duty cycle instead, driven with
+    /// `Drive::TimeProportioned { window_ms: 600_000 }`, windows long
+    /// against the cycling limits.
+    const CONTROL: Control = Control::OnOff {
+        hysteresis: Hysteresis {
+            upper: Temperature::from_centi_celsius(50),
+            lower: Temperature::from_centi_celsius(50),
+        },
+    };
+
+    /// Within what the pump manufacturer rates for continuous duty.
+    const CYCLING: Limits = Limits {
+        min_on_ms: 30_000,
+        min_off_ms: 60_000,
+        max_starts_per_hour: 12,
+    };
+
+    /// From checking the probe against a reference thermometer.
+    const TEMP_SENSOR_CALIBRATION: Calibration = Calibration {
+        offset: Temperature::from_centi_celsius(-15),
+        gain: Gain::from_per_ten_thousand(10_020),
+    };
+
     const VOS_CFG: pwr::VoltageScale = pwr::VoltageScale::Range1 { enable_boost: true };
 
     const PLL_CFG: rcc::PllConfig = {
@@ -120,7 +211,8 @@ mod app {
         let gpiob = ctx.device.GPIOB.split(&mut rcc);
 
         let usart_cfg = serial::FullConfig::default()
-            .baudrate(time::Bps(9600)) // so strange
+            // links come up slow and negotiate a faster rate
+            .baudrate(time::Bps(BaudRate::DEFAULT.as_bps()))
             .receiver_timeout_us(1000);
 
         // HAL: USART configuration validation should absolutely be const
@@ -141,6 +233,15 @@ mod app {
         ))
         .split();
 
+        // SAFETY: both USARTs run at the default rate, and each
+        // is handed to the driver owning both of its halves
+        let (line_rate_1, line_rate_2) = unsafe {
+            (
+                LineRate::new(hal::pac::USART1::ptr),
+                LineRate::new(hal::pac::USART2::ptr),
+            )
+        };
+
         let rx1_buf = {
             static mut BUF: [u8; 256] = [0; 256];
 
@@ -183,20 +284,43 @@ mod app {
             SIGNAL.split()
         };
 
-        if let Err(_) = temp::spawn(TempSensor::new(tx1, transfer_in_1, reader1)) {
+        if let Err(_) = temp::spawn(TempSensor::new(
+            TEMP_SENSOR_ADDRESS,
+            tx1,
+            transfer_in_1,
+            reader1,
+            line_rate_1,
+            TEMP_SENSOR_CALIBRATION,
+        )) {
+            fmt::panic!("Failed to spawn task.")
+        }
+
+        if let Err(_) = pump::spawn(Pump::new(
+            PUMP_ADDRESS,
+            tx2,
+            transfer_in_2,
+            reader2,
+            line_rate_2,
+        )) {
             fmt::panic!("Failed to spawn task.")
         }
 
-        if let Err(_) = pump::spawn(Pump::new(tx2, transfer_in_2, reader2)) {
+        if let Err(_) = status::spawn() {
             fmt::panic!("Failed to spawn task.")
         }
 
-        (
-            Shared {
-                model: Model::new(60),
-            },
-            Local { writer1, writer2 },
-        )
+        let setpoint = fmt::unwrap!(Setpoint::new(
+            TARGET_TEMP,
+            TARGET_TEMP_BOUNDS,
+            TARGET_TEMP_RATE
+        ));
+
+        let cycling = fmt::unwrap!(Guard::new(CYCLING));
+
+        let mut model = Model::new(setpoint, CONTROL, cycling);
+        fmt::unwrap!(model.start_schedule(PROFILE));
+
+        (Shared { model }, Local { writer1, writer2 })
     }
 
     #[task(binds = USART1, local = [writer1])]
@@ -219,9 +343,6 @@ mod app {
 
     #[task(shared = [model])]
     async fn temp(ctx: temp::Context, mut temp_sensor: TempSensor) {
-        // for testing purposes
-        Mono::delay(4u64.secs()).await;
-
         fmt::info!("begin...");
 
         match temp_sensor.run(ctx.shared.model).await {
@@ -237,9 +358,6 @@ mod app {
 
     #[task(shared = [model])]
     async fn pump(ctx: pump::Context, mut pump: Pump) {
-        // for testing purposes
-        Mono::delay(4u64.secs()).await;
-
         fmt::info!("begin...");
 
         match pump.run(ctx.shared.model).await {
@@ -247,9 +365,33 @@ mod app {
                 // shutdown
             }
             Err(fault) => {
-                // handle fault
+                // the intended escalation of faults which cannot be
+                // cleared, the pump stays latched off until serviced
                 fmt::panic!("{}", fault);
             }
         }
     }
+
+    #[task(shared = [model])]
+    async fn status(mut ctx: status::Context) {
+        loop {
+            ctx.shared.model.lock(|model| {
+                fmt::info!(
+                    "target temperature: {}, towards {}",
+                    model.target_temp(),
+                    model.final_target_temp()
+                );
+
+                if let Some(telemetry) = model.telemetry() {
+                    fmt::info!("pump telemetry: {}", telemetry);
+                }
+
+                if let Some(progress) = model.schedule_progress() {
+                    fmt::info!("schedule: {}", progress);
+                }
+            });
+
+            Mono::delay(STATUS_PERIOD_MS.millis()).await;
+        }
+    }
 }
@@ -1,32 +1,76 @@
 use heapless::HistoryBuffer;
 use rtic_monotonics::Monotonic;
 
-use common::types::{pump::PumpState, temperature::Temperature};
+use common::types::{
+    pump::{PumpState, Speed, Telemetry},
+    temperature::Temperature,
+};
+
+use control::{
+    cycling::{Deferral, Guard},
+    pid::Drive,
+    schedule::{Progress, Schedule, Segment, Status},
+    setpoint::{OutOfBounds, Setpoint},
+    Control,
+};
 
 use crate::{app::Mono, fmt};
 
+/// Below this output, in percent, the pump is
+/// turned off rather than barely turning.
+const MIN_OUTPUT: f32 = 1.;
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Entry {
-    #[allow(unused)] // unused in example implementation
     timestamp: <Mono as Monotonic>::Instant,
-    temperature: Temperature,
-    #[allow(unused)] // unused in example implementation
+    /// [`None`] if the sensor faulted.
+    temperature: Option<Temperature>,
     pump_state: PumpState,
+    telemetry: Option<Telemetry>,
 }
 
 pub struct Model {
-    target_temp: Temperature,
+    setpoint: Setpoint,
+    /// Overrides the setpoint while running.
+    schedule: Schedule,
+    /// Whether the schedule is paused for want of readings,
+    /// rather than by [`Model::pause_schedule`] from outside.
+    schedule_held: bool,
+
+    control: Control,
+    /// The latest output of the PID controller in percent,
+    /// [`None`] without a reading to base it on.
+    output: Option<f32>,
+    /// When the PID controller was last updated.
+    last_update: Option<<Mono as Monotonic>::Instant>,
+
+    /// Keeps the pump from cycling too fast.
+    cycling: Guard,
+    /// The transition last held back by [`Model::cycling`] and why,
+    /// [`None`] once the pump follows the control again.
+    deferred: Option<(PumpState, Deferral)>,
 
     history: HistoryBuffer<Entry, 8>,
-    pending: (Option<Temperature>, Option<PumpState>),
+    pending: (
+        Option<Option<Temperature>>,
+        Option<PumpState>,
+        Option<Telemetry>,
+    ),
 }
 
 impl Model {
-    pub const fn new(target_temp: Temperature) -> Self {
+    pub const fn new(setpoint: Setpoint, control: Control, cycling: Guard) -> Self {
         Self {
-            target_temp,
+            setpoint,
+            schedule: Schedule::IDLE,
+            schedule_held: false,
+            control,
+            output: None,
+            last_update: None,
+            cycling,
+            deferred: None,
             history: HistoryBuffer::new(),
-            pending: (None, None),
+            pending: (None, None, None),
         }
     }
 
@@ -38,17 +82,178 @@ impl Model {
             return;
         };
 
+        let timestamp = Mono::now();
+
         self.history.write(Entry {
-            timestamp: Mono::now(),
+            timestamp,
             temperature,
             pump_state,
+            telemetry: self.pending.2,
         });
 
-        self.pending = (None, None);
+        self.pending = (None, None, None);
+
+        self.update_control();
+    }
+
+    /// Feed the newest entry to the PID controller, if any.
+    fn update_control(&mut self) {
+        let Some(entry) = self.history.recent() else {
+            return;
+        };
+
+        let setpoint = self.target_at(millis(entry.timestamp));
+
+        let Control::Pid { pid, .. } = &mut self.control else {
+            return;
+        };
+
+        let Some(temperature) = entry.temperature else {
+            // a gap in the readings, start
+            // over once they come back
+            pid.reset();
+            self.output = None;
+            self.last_update = None;
+
+            return;
+        };
+
+        let dt_ms = match self.last_update {
+            Some(last) => (entry.timestamp - last).to_millis() as u32,
+            None => 0,
+        };
+
+        let output = pid.update(celsius(setpoint), celsius(temperature), dt_ms);
+        fmt::debug!(
+            "pid output: {}% towards {} after {} ms",
+            output,
+            setpoint,
+            dt_ms
+        );
+
+        self.output = Some(output);
+        self.last_update = Some(entry.timestamp);
+    }
+
+    /// The target temperature currently in effect,
+    /// from the schedule if one is running.
+    pub fn target_temp(&self) -> Temperature {
+        self.target_at(millis(Mono::now()))
+    }
+
+    /// The target temperature [`Model::target_temp`]
+    /// is ramping towards outside of schedules.
+    pub fn final_target_temp(&self) -> Temperature {
+        self.setpoint.target()
+    }
+
+    fn target_at(&self, now_ms: u64) -> Temperature {
+        match self.schedule.target(now_ms) {
+            Some(target) => target,
+            None => self.setpoint.active(now_ms),
+        }
+    }
+
+    /// Run a ramp/soak profile, which takes over from the
+    /// setpoint until it finishes or is aborted.
+    ///
+    /// It is paused while the sensor is faulted, so soak
+    /// time only counts with readings to control on.
+    pub fn start_schedule(&mut self, profile: &'static [Segment]) -> Result<(), OutOfBounds> {
+        let bounds = self.setpoint.bounds();
+
+        // the same limits apply as for the setpoint
+        if let Some(segment) = profile
+            .iter()
+            .find(|segment| !bounds.contains(segment.temperature()))
+        {
+            return Err(OutOfBounds {
+                requested: segment.temperature(),
+                bounds,
+            });
+        }
+
+        let now_ms = millis(Mono::now());
+
+        // the first ramp starts from the current target
+        self.schedule.start(profile, self.target_at(now_ms), now_ms);
+        self.schedule_held = false;
+        fmt::info!("schedule started with {} segments", profile.len());
+
+        Ok(())
+    }
+
+    pub fn pause_schedule(&mut self) {
+        self.schedule.pause(millis(Mono::now()));
+    }
+
+    pub fn resume_schedule(&mut self) {
+        self.schedule.resume(millis(Mono::now()));
+    }
+
+    /// Stop the schedule, the setpoint takes over at its
+    /// rate from wherever the schedule left the target.
+    pub fn abort_schedule(&mut self) {
+        let now_ms = millis(Mono::now());
+
+        if let Some(target) = self.schedule.target(now_ms) {
+            self.setpoint.ramp_from(target, now_ms);
+        }
+
+        self.schedule.abort();
+        self.schedule_held = false;
+        fmt::info!("schedule stopped");
+    }
+
+    /// Keep the schedule in step with the sensor,
+    /// `reading` telling whether it produced one.
+    fn update_schedule(&mut self, reading: bool) {
+        let Some(progress) = self.schedule_progress() else {
+            return;
+        };
+
+        match (progress.status, reading) {
+            (Status::Running, false) => {
+                self.pause_schedule();
+                self.schedule_held = true;
+            }
+            (Status::Paused, true) if self.schedule_held => {
+                self.resume_schedule();
+                self.schedule_held = false;
+            }
+            // the setpoint takes over once the profile is done
+            (Status::Finished, _) => self.abort_schedule(),
+            _ => {}
+        }
+    }
+
+    /// The current segment of the schedule and the
+    /// time remaining, [`None`] if none is running.
+    pub fn schedule_progress(&self) -> Option<Progress> {
+        self.schedule.progress(millis(Mono::now()))
+    }
+
+    /// The latest telemetry, [`None`] if none
+    /// was polled within the history.
+    pub fn telemetry(&self) -> Option<Telemetry> {
+        self.history
+            .oldest_ordered()
+            .filter_map(|entry| entry.telemetry)
+            .last()
     }
 
     pub fn push_temperature(&mut self, temp: Temperature) {
-        self.pending.0.replace(temp);
+        self.pending.0.replace(Some(temp));
+        self.update_schedule(true);
+
+        self.try_push_pending();
+    }
+
+    /// The sensor faulted, so there is no
+    /// temperature for this entry.
+    pub fn push_missing_temperature(&mut self) {
+        self.pending.0.replace(None);
+        self.update_schedule(false);
 
         self.try_push_pending();
     }
@@ -59,12 +264,51 @@ impl Model {
         self.try_push_pending();
     }
 
-    pub fn pump_target(&self) -> PumpState {
+    /// Telemetry is polled less often than the
+    /// pump state so it does not complete an entry,
+    /// it is attached to the next one instead.
+    pub fn push_telemetry(&mut self, telemetry: Telemetry) {
+        self.pending.2.replace(telemetry);
+    }
+
+    /// The state the pump should be in, unless that
+    /// would cycle it too fast, see [`Model::deferred`].
+    pub fn pump_target(&mut self) -> PumpState {
+        let target = self.control_target();
+
+        // the guard learns of the transition as it is commanded,
+        // not once the pump reports it in a complete entry
+        match self.cycling.request(millis(Mono::now()), target) {
+            Ok(()) => {
+                self.deferred = None;
+
+                target
+            }
+            Err(deferral) => {
+                self.deferred = Some((target, deferral));
+
+                // only transitions are deferred, so
+                // the pump stays in the other state
+                match target {
+                    PumpState::On => PumpState::Off,
+                    PumpState::Off => PumpState::On,
+                }
+            }
+        }
+    }
+
+    /// The transition [`Model::pump_target`] is holding back and why.
+    pub fn deferred(&self) -> Option<(PumpState, Deferral)> {
+        self.deferred
+    }
+
+    /// The state the control asks for.
+    fn control_target(&self) -> PumpState {
         // some function of the history
         // will determine the appropriate
-        // next pump state. knowing nothing
-        // about the system i will use the
-        // simplest possible control scheme
+        // next pump state, either the PID
+        // output or, for on/off control,
+        // the side of the bands it is on
 
         let Some(entry) = self.history.oldest_ordered().last() else {
             // cool by default because likely
@@ -72,14 +316,70 @@ impl Model {
             return PumpState::On;
         };
 
-        let target = if entry.temperature > self.target_temp {
-            PumpState::On
-        } else {
-            PumpState::Off
+        let Some(temperature) = entry.temperature else {
+            // same as above, without a reading
+            // there is nothing to go on
+            fmt::info!("last entry: {}, no reading, cooling", entry);
+            return PumpState::On;
+        };
+
+        let target = match (&self.control, self.output) {
+            (Control::Pid { drive, .. }, Some(output)) => match drive {
+                Drive::Speed if output < MIN_OUTPUT => PumpState::Off,
+                Drive::Speed => PumpState::On,
+                Drive::TimeProportioned { window_ms } => {
+                    let window_ms = (*window_ms).max(1) as u64;
+                    let elapsed_ms = millis(Mono::now());
+
+                    // on for the first `output` percent of every window
+                    let phase = (elapsed_ms % window_ms) as f32 / window_ms as f32;
+
+                    if phase * 100. < output {
+                        PumpState::On
+                    } else {
+                        PumpState::Off
+                    }
+                }
+            },
+            (Control::OnOff { hysteresis }, _) => {
+                hysteresis.target(self.target_temp(), temperature, entry.pump_state)
+            }
+            // no output without a reading, handled above
+            (Control::Pid { .. }, None) => PumpState::On,
         };
 
         fmt::info!("last entry: {}, target: {}", entry, target);
 
         target
     }
+
+    pub fn pump_speed(&self) -> Speed {
+        match (&self.control, self.output) {
+            (
+                Control::Pid {
+                    drive: Drive::Speed,
+                    ..
+                },
+                Some(output),
+            ) => {
+                // round to the nearest percent, casts saturate,
+                // the pump may be kept running below `MIN_OUTPUT`
+                // while switching it off is deferred
+                Speed::from_percent((output.max(MIN_OUTPUT) + 0.5) as u8)
+            }
+            // on/off control, time proportioning and
+            // missing readings run the pump flat out
+            _ => Speed::FULL,
+        }
+    }
+}
+
+/// Milliseconds since boot as the cycling guard works with them.
+fn millis(instant: <Mono as Monotonic>::Instant) -> u64 {
+    instant.duration_since_epoch().to_millis()
+}
+
+/// Degrees Celsius as the PID controller works with them.
+fn celsius(temperature: Temperature) -> f32 {
+    temperature.as_centi_celsius() as f32 / 100.
 }
@@ -1,2 +1,3 @@
+pub mod link;
 pub mod pump;
 pub mod temperature;
@@ -0,0 +1,522 @@
+use core::marker::PhantomData;
+
+use cookie_cutter::SerializeIter;
+use embedded_command::command_buffer::{error::Overflow, CommandBuffer};
+use rtic_monotonics::{fugit::ExtU64, Monotonic};
+use rtic_sync::signal::SignalReader;
+use stm32g4xx_hal::pac::usart1::RegisterBlock;
+
+use crate::{
+    app::{Mono, TransferIn1, TransferIn2, Tx1, Tx2},
+    fmt,
+};
+use common::{
+    command::{Address, NackReason, TransactionId, POINT_TO_POINT},
+    frame,
+    types::{
+        device::{Identity, Kind},
+        link::{self, BaudRate},
+    },
+};
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    Ingestion(Overflow),
+    Deserialize(cookie_cutter::error::Error),
+    Corrupt,
+    Timeout,
+    NonConformance,
+    Incompatible(Identity),
+    /// The peripheral refused the request.
+    Nack(NackReason),
+}
+
+impl From<Overflow> for Error {
+    fn from(value: Overflow) -> Self {
+        Self::Ingestion(value)
+    }
+}
+
+impl From<cookie_cutter::error::Error> for Error {
+    fn from(value: cookie_cutter::error::Error) -> Self {
+        Self::Deserialize(value)
+    }
+}
+
+impl From<cookie_cutter::error::EndOfInput> for Error {
+    fn from(_value: cookie_cutter::error::EndOfInput) -> Self {
+        Self::Deserialize(cookie_cutter::error::Error::EndOfInput)
+    }
+}
+
+impl From<rtic_monotonics::TimeoutError> for Error {
+    fn from(_value: rtic_monotonics::TimeoutError) -> Self {
+        Self::Timeout
+    }
+}
+
+/// Whatever the logging backend can print.
+#[cfg(feature = "defmt")]
+pub trait Log: defmt::Format {}
+#[cfg(feature = "defmt")]
+impl<T: defmt::Format> Log for T {}
+
+/// Whatever the logging backend can print.
+#[cfg(not(feature = "defmt"))]
+pub trait Log {}
+#[cfg(not(feature = "defmt"))]
+impl<T> Log for T {}
+
+/// What a link needs to know of the protocol spoken over it,
+/// implemented for each protocol with [`protocol!`].
+pub trait Protocol {
+    type ToPeripheral: Copy + SerializeIter + Log;
+    type FromPeripheral: SerializeIter;
+    type Request: SerializeIter;
+    type Response: SerializeIter;
+
+    /// What the peripheral has to identify as.
+    const KIND: Kind;
+    const VERSION: u8;
+    const IDENTIFY: Self::ToPeripheral;
+
+    fn set_baud(rate: BaudRate) -> Self::ToPeripheral;
+
+    fn request(address: Address, id: TransactionId, command: Self::ToPeripheral) -> Self::Request;
+    fn response(response: Self::Response) -> (Address, TransactionId, Self::FromPeripheral);
+
+    fn answered_by(command: &Self::ToPeripheral, response: &Self::FromPeripheral) -> bool;
+
+    fn nack(response: &Self::FromPeripheral) -> Option<NackReason>;
+    fn identity(response: Self::FromPeripheral) -> Option<Identity>;
+    fn baud(response: Self::FromPeripheral) -> Option<BaudRate>;
+}
+
+/// Implements [`Protocol`] for the messages in `common::command::$module`.
+macro_rules! protocol {
+    ($(#[$attr:meta])* $name:ident, $module:ident, $kind:expr) => {
+        $(#[$attr])*
+        pub enum $name {}
+
+        impl $crate::peripherals::link::Protocol for $name {
+            type ToPeripheral = common::command::$module::ToPeripheral;
+            type FromPeripheral = common::command::$module::FromPeripheral;
+            type Request = common::command::$module::Request;
+            type Response = common::command::$module::Response;
+
+            const KIND: common::types::device::Kind = $kind;
+            const VERSION: u8 = common::command::$module::PROTOCOL_VERSION;
+            const IDENTIFY: Self::ToPeripheral = common::command::$module::ToPeripheral::Identify;
+
+            fn set_baud(rate: common::types::link::BaudRate) -> common::command::$module::ToPeripheral {
+                common::command::$module::ToPeripheral::SetBaud(rate)
+            }
+
+            fn request(
+                address: common::command::Address,
+                id: common::command::TransactionId,
+                command: Self::ToPeripheral,
+            ) -> common::command::$module::Request {
+                common::command::$module::Request {
+                    address,
+                    id,
+                    command,
+                }
+            }
+
+            fn response(
+                response: Self::Response,
+            ) -> (
+                common::command::Address,
+                common::command::TransactionId,
+                Self::FromPeripheral,
+            ) {
+                (response.address, response.id, response.command)
+            }
+
+            fn answered_by(command: &Self::ToPeripheral, response: &Self::FromPeripheral) -> bool {
+                command.answered_by(response)
+            }
+
+            fn nack(response: &Self::FromPeripheral) -> Option<common::command::NackReason> {
+                match response {
+                    common::command::$module::FromPeripheral::Nack { reason } => Some(*reason),
+                    _ => None,
+                }
+            }
+
+            fn identity(response: Self::FromPeripheral) -> Option<common::types::device::Identity> {
+                match response {
+                    common::command::$module::FromPeripheral::Identity(identity) => Some(identity),
+                    _ => None,
+                }
+            }
+
+            fn baud(response: Self::FromPeripheral) -> Option<common::types::link::BaudRate> {
+                match response {
+                    common::command::$module::FromPeripheral::Baud(rate) => Some(rate),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use protocol;
+
+/// The halves of a USART a link runs over.
+pub trait Port {
+    /// Blocks until `bytes` are out.
+    fn write(&mut self, bytes: &[u8]);
+    fn start(&mut self);
+    /// Hand what the transfer received to `buf` and start over.
+    fn receive(&mut self, buf: &mut CommandBuffer<256>) -> Result<(), Overflow>;
+    /// Stop receiving until the next [`Port::restart`].
+    fn pause(&mut self);
+    /// Drop what the transfer received so far.
+    fn restart(&mut self);
+}
+
+/// The HAL types of each USART differ, but not what they can do.
+macro_rules! port {
+    ($tx:ty, $transfer_in:ty) => {
+        impl Port for ($tx, $transfer_in) {
+            fn write(&mut self, bytes: &[u8]) {
+                use stm32g4xx_hal::{block, hal::serial::Write as _};
+
+                for byte in bytes {
+                    fmt::unwrap!(block!(self.0.write(*byte)));
+                }
+
+                fmt::unwrap!(block!(self.0.flush()));
+            }
+
+            fn start(&mut self) {
+                self.1.start(|_| {});
+            }
+
+            fn receive(&mut self, command_buf: &mut CommandBuffer<256>) -> Result<(), Overflow> {
+                self.1.peek_buffer(|buf, remaining| {
+                    fmt::trace!("buf: {}", buf[..buf.len() - remaining]);
+
+                    command_buf.ingest(buf[..buf.len() - remaining].iter())?;
+
+                    Ok::<_, Overflow>(())
+                })?;
+
+                self.1.restart(|_| {});
+
+                Ok(())
+            }
+
+            fn pause(&mut self) {
+                self.1.pause(|_| {});
+            }
+
+            fn restart(&mut self) {
+                self.1.restart(|_| {});
+            }
+        }
+    };
+}
+
+port!(Tx1, TransferIn1);
+port!(Tx2, TransferIn2);
+
+/// Framed request/response exchanges with the
+/// peripheral at one address over one USART.
+pub struct Link<P: Protocol, T: Port> {
+    port: T,
+
+    signal: SignalReader<'static, ()>,
+    command_buf: CommandBuffer<256>,
+    line_rate: LineRate,
+
+    address: Address,
+    next_id: TransactionId,
+    stale_responses: u32,
+
+    protocol: PhantomData<P>,
+}
+
+impl<P: Protocol, T: Port> Link<P, T> {
+    pub const fn new(
+        address: Address,
+        port: T,
+        signal: SignalReader<'static, ()>,
+        line_rate: LineRate,
+    ) -> Self {
+        Self {
+            port,
+
+            signal,
+            command_buf: CommandBuffer::new(),
+            line_rate,
+
+            address,
+            next_id: 0,
+            stale_responses: 0,
+
+            protocol: PhantomData,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.port.start();
+    }
+
+    pub fn write_command(&mut self, command: P::ToPeripheral) -> Result<TransactionId, Error> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        let mut buf = [0; frame::MAX_FRAME];
+        let n = frame::encode(&P::request(self.address, id, command), &mut buf)?;
+
+        self.port.write(&buf[..n]);
+
+        Ok(id)
+    }
+
+    async fn receive(&mut self) -> Result<(), Error> {
+        self.signal.wait().await;
+
+        self.port.receive(&mut self.command_buf)?;
+
+        Ok(())
+    }
+
+    pub async fn read_command(&mut self, id: TransactionId) -> Result<P::FromPeripheral, Error> {
+        loop {
+            let result = {
+                let mut iter = self.command_buf.iter();
+
+                let result = frame::decode::<P::Response>(&mut iter);
+
+                // incomplete frames stay buffered
+                if !matches!(result, Err(frame::Error::EndOfInput)) {
+                    let memento = iter.capture();
+                    self.command_buf.flush(memento);
+                }
+
+                result
+            };
+
+            let (address, response_id, command) = match result {
+                // special case
+                Err(frame::Error::EndOfInput) => {
+                    self.receive().await?;
+                    continue;
+                }
+                // the bad frame is dropped, the next
+                // one starts after its delimiter
+                Err(frame::Error::Corrupt) => break Err(Error::Corrupt),
+
+                Ok(response) => P::response(response),
+                Err(frame::Error::Deserialize(e)) => break Err(e.into()),
+            };
+
+            // another peripheral on the bus
+            if self.address != POINT_TO_POINT && address != self.address {
+                fmt::trace!("ignored response from {}", address);
+
+                continue;
+            }
+
+            // a late reply to an earlier request
+            if response_id != id {
+                self.stale_responses += 1;
+                fmt::warn!(
+                    "discarded stale response: id {}, expected {} ({} total)",
+                    response_id,
+                    id,
+                    self.stale_responses
+                );
+
+                continue;
+            }
+
+            if let Some(reason) = P::nack(&command) {
+                break Err(Error::Nack(reason));
+            }
+
+            break Ok(command);
+        }
+    }
+
+    /// Send a command and wait for its answer.
+    pub async fn transact(&mut self, command: P::ToPeripheral) -> Result<P::FromPeripheral, Error> {
+        // 1. send command
+        let id = self.write_command(command)?;
+        fmt::trace!("sent cmd: {}", command);
+
+        // 2. receive response or timeout
+        let response = Mono::timeout_after(100u64.millis(), self.read_command(id)).await??;
+
+        // 3. make sure it answers the command
+        if !P::answered_by(&command, &response) {
+            return Err(Error::NonConformance);
+        }
+
+        Ok(response)
+    }
+
+    pub async fn identify(&mut self) -> Result<Identity, Error> {
+        // 1. send identify command and receive identity or timeout
+        P::identity(self.transact(P::IDENTIFY).await?).ok_or(Error::NonConformance)
+    }
+
+    /// Wait for the peripheral to come up and make sure it is
+    /// what we expect, speaking our revision of the protocol.
+    pub async fn handshake(&mut self) -> Result<(), Error> {
+        let mut attempts = 0;
+
+        let identity = loop {
+            match self.identify().await {
+                Ok(identity) => break identity,
+                // the peripheral may still be booting
+                Err(
+                    Error::Timeout
+                    | Error::Corrupt
+                    | Error::Nack(NackReason::Busy | NackReason::NotReady),
+                ) if attempts < 10 => {
+                    attempts += 1;
+                    Mono::delay(500u64.millis()).await;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        fmt::info!("identified: {}", identity);
+
+        if identity.kind != P::KIND || identity.protocol != P::VERSION {
+            return Err(Error::Incompatible(identity));
+        }
+
+        Ok(())
+    }
+
+    /// Drop whatever was received so far.
+    fn discard(&mut self) {
+        self.command_buf = CommandBuffer::new();
+        self.port.restart();
+    }
+
+    /// Switch the USART to `rate`, with the transfer stopped
+    /// so it does not pick up garbage while the divider changes.
+    fn set_rate(&mut self, rate: BaudRate) {
+        self.port.pause();
+        self.line_rate.set(rate);
+        self.discard();
+    }
+
+    /// Whether the peripheral answers at the current rate.
+    async fn confirm(&mut self) -> Result<bool, Error> {
+        self.discard();
+
+        match self.identify().await {
+            Ok(_) => Ok(true),
+            Err(Error::Timeout | Error::Corrupt | Error::Deserialize(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Switch the link to `rate` in lockstep with the peripheral.
+    ///
+    /// Returns the rate the link settled on. The default always
+    /// works, so a peripheral which refuses `rate` or loses track
+    /// of the switch leaves the link at the default.
+    pub async fn negotiate_baud(&mut self, rate: BaudRate) -> Result<BaudRate, Error> {
+        match self.switch_baud(rate).await {
+            Ok(rate) => Ok(rate),
+            // the peripheral may not support the rate
+            Err(Error::Timeout | Error::Corrupt | Error::NonConformance | Error::Nack(_)) => {
+                fmt::warn!("failed to negotiate baud rate");
+
+                self.set_rate(BaudRate::DEFAULT);
+
+                // give the peripheral time to fall back should it have switched
+                Mono::delay((link::CONFIRM_TIMEOUT_MS as u64).millis()).await;
+
+                Ok(BaudRate::DEFAULT)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// [`Link::negotiate_baud`], failing if the link cannot
+    /// be confirmed at either rate.
+    async fn switch_baud(&mut self, rate: BaudRate) -> Result<BaudRate, Error> {
+        // 1. propose the rate, the answer still comes at the old one
+        match P::baud(self.transact(P::set_baud(rate)).await?) {
+            Some(accepted) if accepted == rate => {}
+            _ => return Err(Error::NonConformance),
+        }
+
+        // 2. follow the peripheral, which switches once its answer is out
+        Mono::delay(10u64.millis()).await;
+        self.set_rate(rate);
+
+        // 3. the first exchange at the new rate confirms it
+        if self.confirm().await? {
+            return Ok(rate);
+        }
+
+        // 4. otherwise the peripheral falls back once it gives up waiting
+        fmt::warn!("no answer at {} bps, falling back", rate.as_bps());
+
+        self.set_rate(BaudRate::DEFAULT);
+        Mono::delay((link::CONFIRM_TIMEOUT_MS as u64).millis()).await;
+
+        if self.confirm().await? {
+            return Ok(BaudRate::DEFAULT);
+        }
+
+        // 5. unless it did hear the confirmation, and only its answer got lost
+        self.set_rate(rate);
+
+        if self.confirm().await? {
+            return Ok(rate);
+        }
+
+        self.set_rate(BaudRate::DEFAULT);
+
+        Err(Error::Timeout)
+    }
+}
+
+/// Line rate control of a USART the HAL already split into halves,
+/// which it offers no way to reconfigure.
+pub struct LineRate {
+    /// The `ptr` of the USART, which unlike a reference is `Send`.
+    usart: fn() -> *const RegisterBlock,
+    /// The divider the HAL programmed for [`BaudRate::DEFAULT`].
+    default_brr: u32,
+}
+
+impl LineRate {
+    /// # Safety
+    ///
+    /// The USART must have been configured for [`BaudRate::DEFAULT`],
+    /// and only the owner of both its halves may change its rate.
+    pub unsafe fn new(usart: fn() -> *const RegisterBlock) -> Self {
+        Self {
+            usart,
+            default_brr: (*usart()).brr.read().bits(),
+        }
+    }
+
+    /// Only call between transfers with the receiving one
+    /// stopped, a frame in flight is garbled.
+    pub fn set(&mut self, rate: BaudRate) {
+        // SAFETY: exclusive by contract of `new`
+        let usart = unsafe { &*(self.usart)() };
+
+        // scaling the default divider saves knowing the kernel clock
+        let brr = self.default_brr * BaudRate::DEFAULT.as_bps() / rate.as_bps();
+
+        // the divider only takes while the USART is disabled
+        usart.cr1.modify(|_, w| w.ue().clear_bit());
+        usart.brr.write(|w| unsafe { w.bits(brr) });
+        usart.cr1.modify(|_, w| w.ue().set_bit());
+    }
+}
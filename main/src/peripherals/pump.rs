@@ -1,5 +1,3 @@
-use cookie_cutter::SerializeIter;
-use embedded_command::command_buffer::CommandBuffer;
 use futures::future::try_join;
 use rtic::Mutex;
 use rtic_monotonics::{fugit::ExtU64, Monotonic};
@@ -9,160 +7,283 @@ use crate::{
     app::{Mono, TransferIn2, Tx2},
     fmt,
     model::Model,
+    peripherals::link::{self, LineRate, Link},
 };
 use common::{
-    command::pump::{Fault, FromPeripheral, ToPeripheral},
-    types::pump::PumpState,
+    command::{
+        pump::{Fault, FromPeripheral, ToPeripheral},
+        Address, NackReason,
+    },
+    types::{
+        device::{Identity, Kind},
+        link::BaudRate,
+        pump::{Failsafe, PumpState, Speed, Telemetry},
+    },
 };
 
+/// Should this driver stop talking to the pump,
+/// run it to cool, as cool is likely safe.
+const FAILSAFE: Failsafe = Failsafe {
+    timeout_ms: 1000,
+    safe_state: PumpState::On,
+};
+
+/// Failed attempts in a row at clearing a fault
+/// before it is escalated, see [`Pump::run`].
+const MAX_FAULT_CLEARS: u8 = 3;
+
+/// The line rate proposed once the pump is identified.
+const BAUD_RATE: BaudRate = BaudRate::Bps115200;
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error {
     TransferInProgress,
     Ingestion(embedded_command::command_buffer::error::Overflow),
     Deserialize(cookie_cutter::error::Error),
+    Corrupt,
     Timeout,
     Fault(Fault),
     NonConformance,
+    Incompatible(Identity),
+    /// The peripheral refused the request.
+    Nack(NackReason),
 }
 
-impl From<embedded_command::command_buffer::error::Overflow> for Error {
-    fn from(value: embedded_command::command_buffer::error::Overflow) -> Self {
-        Self::Ingestion(value)
-    }
-}
-
-impl From<cookie_cutter::error::Error> for Error {
-    fn from(value: cookie_cutter::error::Error) -> Self {
-        Self::Deserialize(value)
-    }
-}
-
-impl From<cookie_cutter::error::EndOfInput> for Error {
-    fn from(_value: cookie_cutter::error::EndOfInput) -> Self {
-        Self::Deserialize(cookie_cutter::error::Error::EndOfInput)
+impl From<link::Error> for Error {
+    fn from(value: link::Error) -> Self {
+        match value {
+            link::Error::Ingestion(e) => Self::Ingestion(e),
+            link::Error::Deserialize(e) => Self::Deserialize(e),
+            link::Error::Corrupt => Self::Corrupt,
+            link::Error::Timeout => Self::Timeout,
+            link::Error::NonConformance => Self::NonConformance,
+            link::Error::Incompatible(identity) => Self::Incompatible(identity),
+            link::Error::Nack(reason) => Self::Nack(reason),
+        }
     }
 }
 
-impl From<rtic_monotonics::TimeoutError> for Error {
-    fn from(_value: rtic_monotonics::TimeoutError) -> Self {
-        Self::Timeout
-    }
-}
+link::protocol!(
+    /// The pump protocol as spoken over a [`Link`].
+    PumpProtocol,
+    pump,
+    Kind::Pump
+);
 
 pub struct Pump {
-    tx: Tx2,
-
-    transfer_in: TransferIn2,
+    link: Link<PumpProtocol, (Tx2, TransferIn2)>,
 
-    signal: SignalReader<'static, ()>,
-    command_buf: CommandBuffer<256>,
+    /// Failed attempts at clearing the current fault.
+    fault_clears: u8,
 }
 
 impl Pump {
-    pub const fn new(tx: Tx2, transfer_in: TransferIn2, signal: SignalReader<'static, ()>) -> Self {
+    pub const fn new(
+        address: Address,
+        tx: Tx2,
+        transfer_in: TransferIn2,
+        signal: SignalReader<'static, ()>,
+        line_rate: LineRate,
+    ) -> Self {
         Self {
-            tx,
-            transfer_in,
+            link: Link::new(address, (tx, transfer_in), signal, line_rate),
 
-            signal,
-            command_buf: CommandBuffer::new(),
+            fault_clears: 0,
         }
     }
 
-    fn write_command(&mut self, command: ToPeripheral) -> Result<(), Error> {
-        use stm32g4xx_hal::{block, hal::serial::Write as _};
+    pub async fn update_pump(&mut self, target: PumpState, speed: Speed) -> Result<(), Error> {
+        // 1. send pump state to pump and validate response
+        match self.link.transact(ToPeripheral::Set(target)).await? {
+            FromPeripheral::PumpState(state) => {
+                fmt::trace!("received state: {}", state);
+
+                if state != target {
+                    return Err(Error::NonConformance);
+                }
+            }
+            FromPeripheral::Fault(fault) => return Err(Error::Fault(fault)),
+            _ => return Err(Error::NonConformance),
+        }
 
-        let mut buf = [0; 8];
-        let mut n = 0;
-        command.serialize_iter(buf.iter_mut().inspect(|_| {
-            n += 1;
-        }))?;
+        // 2. send pump speed to pump and validate acknowledged speed
+        match self.link.transact(ToPeripheral::SetSpeed(speed)).await? {
+            FromPeripheral::Speed(acknowledged) => {
+                fmt::trace!("received speed: {}", acknowledged);
 
-        for byte in &buf[..n] {
-            fmt::unwrap!(block!(self.tx.write(*byte)));
+                if acknowledged == speed {
+                    Ok(())
+                } else {
+                    Err(Error::NonConformance)
+                }
+            }
+            FromPeripheral::Fault(fault) => Err(Error::Fault(fault)),
+            _ => Err(Error::NonConformance),
         }
+    }
 
-        fmt::unwrap!(block!(self.tx.flush()));
+    pub async fn configure_failsafe(&mut self, failsafe: Failsafe) -> Result<(), Error> {
+        // 1. send failsafe configuration and validate the failsafe in effect
+        match self
+            .link
+            .transact(ToPeripheral::ConfigureFailsafe(failsafe))
+            .await?
+        {
+            FromPeripheral::Failsafe(acknowledged) if acknowledged == failsafe => {
+                fmt::info!("failsafe armed: {}", acknowledged);
 
-        Ok(())
+                Ok(())
+            }
+            _ => Err(Error::NonConformance),
+        }
     }
 
-    async fn read_command(&mut self) -> Result<FromPeripheral, Error> {
-        loop {
-            self.signal.wait().await;
+    pub async fn heartbeat(&mut self) -> Result<(), Error> {
+        // 1. send heartbeat, the pump answers with its state
+        match self.link.transact(ToPeripheral::Heartbeat).await? {
+            FromPeripheral::PumpState(_) => Ok(()),
+            FromPeripheral::Fault(fault) => Err(Error::Fault(fault)),
+            _ => Err(Error::NonConformance),
+        }
+    }
 
-            self.transfer_in.peek_buffer(|buf, remaining| {
-                fmt::trace!("buf: {}", buf[..buf.len() - remaining]);
+    pub async fn read_telemetry(&mut self) -> Result<Telemetry, Error> {
+        // 1. send telemetry request and receive telemetry or timeout
+        match self.link.transact(ToPeripheral::GetTelemetry).await? {
+            FromPeripheral::Telemetry(telemetry) => {
+                fmt::trace!("received telemetry: {}", telemetry);
 
-                self.command_buf
-                    .ingest(buf[..buf.len() - remaining].iter())?;
+                Ok(telemetry)
+            }
+            FromPeripheral::Fault(fault) => Err(Error::Fault(fault)),
+            _ => Err(Error::NonConformance),
+        }
+    }
 
-                Ok::<_, embedded_command::command_buffer::error::Overflow>(())
-            })?;
+    pub async fn clear_fault(&mut self) -> Result<(), Error> {
+        // 1. send clear command, the pump reports
+        // its state once the fault cleared
+        match self.link.transact(ToPeripheral::ClearFault).await? {
+            FromPeripheral::PumpState(state) => {
+                fmt::info!("fault cleared, pump is {}", state);
 
-            self.transfer_in.restart(|_| {});
+                Ok(())
+            }
+            FromPeripheral::Fault(fault) => Err(Error::Fault(fault)),
+            _ => Err(Error::NonConformance),
+        }
+    }
 
-            let mut iter = self.command_buf.iter();
+    /// Try to clear a latched fault, escalating it as
+    /// [`Error::Fault`] if it cannot be cleared.
+    async fn recover(&mut self, fault: Fault) -> Result<(), Error> {
+        fmt::warn!("pump fault: {}", fault);
 
-            let result = FromPeripheral::deserialize_iter(&mut iter);
+        if self.fault_clears >= MAX_FAULT_CLEARS {
+            return Err(Error::Fault(fault));
+        }
 
-            let result = match result {
-                // special case
-                Err(cookie_cutter::error::Error::EndOfInput) => continue,
+        // give the cause a moment to go away, the latched fault
+        // keeps the failsafe from tripping meanwhile, and clearing
+        // it restarts the timeout as any request does
+        Mono::delay(1u64.secs()).await;
 
-                Ok(cmd) => Ok(cmd),
-                Err(e) => Err(e.into()),
-            };
+        match self.clear_fault().await {
+            Ok(()) => {
+                self.fault_clears = 0;
 
-            let memento = iter.capture();
-            self.command_buf.flush(memento);
+                Ok(())
+            }
+            // a persisting fault is retried next cycle
+            Err(Error::Fault(_)) => {
+                self.fault_clears += 1;
 
-            break result;
+                Ok(())
+            }
+            Err(e) => Err(e),
         }
     }
 
-    pub async fn update_pump(&mut self, target: PumpState) -> Result<(), Error> {
-        // 1. send pump state to pump
-        let cmd = ToPeripheral::Set(target);
-        self.write_command(cmd)?;
-        fmt::trace!("sent cmd: {}", cmd);
+    /// Drive the pump from the model until an error stops it.
+    ///
+    /// Latched faults are cleared as they come, a fault which
+    /// survives [`MAX_FAULT_CLEARS`] attempts in a row is returned
+    /// as [`Error::Fault`]. The pump is off and stays off then,
+    /// as a latched fault overrides its failsafe.
+    pub async fn run(&mut self, mut model: impl Mutex<T = Model>) -> Result<(), Error> {
+        self.link.start();
 
-        // 2. validate pump response
-        match Mono::timeout_after(100u64.millis(), self.read_command()).await?? {
-            FromPeripheral::PumpState(state) => {
-                fmt::trace!("received state: {}", state);
+        self.link.handshake().await?;
 
-                if state == target {
-                    Ok(())
-                } else {
-                    Err(Error::NonConformance)
+        let rate = self.link.negotiate_baud(BAUD_RATE).await?;
+        fmt::info!("link running at {} bps", rate.as_bps());
+
+        self.configure_failsafe(FAILSAFE).await?;
+
+        let mut cycle = 0;
+        let mut deferred = None;
+
+        loop {
+            // 1. ask model for target pump state and speed
+            let (pump_target, pump_speed, deferral) =
+                model.lock(|model| (model.pump_target(), model.pump_speed(), model.deferred()));
+
+            // report once per deferral rather than every cycle
+            match (deferred, deferral) {
+                (None, Some((requested, reason))) => {
+                    fmt::warn!("switching pump {} deferred: {}", requested, reason)
                 }
+                (Some((requested, _)), None) => {
+                    fmt::info!("deferred switch to {} released", requested)
+                }
+                _ => {}
             }
-            FromPeripheral::Fault(fault) => Err(Error::Fault(fault)),
-        }
-    }
 
-    pub async fn run(&mut self, mut model: impl Mutex<T = Model>) -> Result<(), Error> {
-        self.transfer_in.start(|_| {});
+            deferred = deferral;
 
-        loop {
-            // 1. ask model for target pump state
-            let pump_target = model.lock(|model| model.pump_target());
+            // telemetry is only polled every few cycles
+            let poll_telemetry = cycle == 0;
+            cycle = (cycle + 1) % 5;
 
             // 2. update pump
-            try_join(self.update_pump(pump_target), async {
-                Mono::delay(200u64.millis()).await;
-                Ok(())
-            })
-            .await
-            .and_then(|(_, _)| {
-                // 3. update model
-                model.lock(|model| {
-                    model.push_pump_state(pump_target);
-                });
-
-                Ok(())
-            })?;
+            let result = try_join(
+                async {
+                    self.heartbeat().await?;
+                    self.update_pump(pump_target, pump_speed).await?;
+
+                    if poll_telemetry {
+                        self.read_telemetry().await.map(Some)
+                    } else {
+                        Ok(None)
+                    }
+                },
+                async {
+                    Mono::delay(200u64.millis()).await;
+                    Ok(())
+                },
+            )
+            .await;
+
+            match result {
+                Ok((telemetry, _)) => {
+                    // 3. update model
+                    model.lock(|model| {
+                        model.push_pump_state(pump_target);
+
+                        if let Some(telemetry) = telemetry {
+                            model.push_telemetry(telemetry);
+                        }
+                    });
+                }
+                // a corrupted reply or a busy peripheral
+                // is not fatal, the next cycle will try again
+                Err(Error::Corrupt) => fmt::warn!("dropped corrupt frame"),
+                Err(Error::Nack(reason @ (NackReason::Busy | NackReason::NotReady))) => {
+                    fmt::warn!("request refused: {}", reason)
+                }
+                Err(Error::Fault(fault)) => self.recover(fault).await?,
+                Err(e) => return Err(e),
+            }
         }
     }
 }
@@ -1,5 +1,3 @@
-use cookie_cutter::SerializeIter;
-use embedded_command::command_buffer::CommandBuffer;
 use futures::future::try_join;
 use rtic::Mutex;
 use rtic_monotonics::{fugit::ExtU64, Monotonic};
@@ -9,35 +7,57 @@ use crate::{
     app::{Mono, TransferIn1, Tx1},
     fmt,
     model::Model,
+    peripherals::link::{self, LineRate, Link},
 };
 use common::{
-    command::temperature::{FromPeripheral, ToPeripheral},
-    types::temperature::Temperature,
+    command::{
+        temperature::{Fault, FromPeripheral, ToPeripheral},
+        Address, NackReason, TransactionId,
+    },
+    types::{
+        device::{Identity, Kind},
+        link::BaudRate,
+        temperature::{Calibration, Temperature},
+    },
 };
 
+/// The line rate proposed once the sensor is identified.
+const BAUD_RATE: BaudRate = BaudRate::Bps115200;
+
+/// How often the sensor is asked to push measurements.
+const STREAM_PERIOD_MS: u16 = 1000;
+
+/// Consecutive missed periods after which the stream is given up on.
+const MAX_MISSED_PERIODS: u8 = 3;
+
+/// Measurements polled before trying to subscribe again.
+const POLLS_BEFORE_RESUBSCRIBE: u32 = 30;
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error {
     TransferInProgress,
     Ingestion(embedded_command::command_buffer::error::Overflow),
     Deserialize(cookie_cutter::error::Error),
+    Corrupt,
     Timeout,
+    NonConformance,
+    Incompatible(Identity),
+    /// The peripheral refused the request.
+    Nack(NackReason),
+    Fault(Fault),
 }
 
-impl From<embedded_command::command_buffer::error::Overflow> for Error {
-    fn from(value: embedded_command::command_buffer::error::Overflow) -> Self {
-        Self::Ingestion(value)
-    }
-}
-
-impl From<cookie_cutter::error::Error> for Error {
-    fn from(value: cookie_cutter::error::Error) -> Self {
-        Self::Deserialize(value)
-    }
-}
-
-impl From<cookie_cutter::error::EndOfInput> for Error {
-    fn from(_value: cookie_cutter::error::EndOfInput) -> Self {
-        Self::Deserialize(cookie_cutter::error::Error::EndOfInput)
+impl From<link::Error> for Error {
+    fn from(value: link::Error) -> Self {
+        match value {
+            link::Error::Ingestion(e) => Self::Ingestion(e),
+            link::Error::Deserialize(e) => Self::Deserialize(e),
+            link::Error::Corrupt => Self::Corrupt,
+            link::Error::Timeout => Self::Timeout,
+            link::Error::NonConformance => Self::NonConformance,
+            link::Error::Incompatible(identity) => Self::Incompatible(identity),
+            link::Error::Nack(reason) => Self::Nack(reason),
+        }
     }
 }
 
@@ -47,110 +67,256 @@ impl From<rtic_monotonics::TimeoutError> for Error {
     }
 }
 
-pub struct TempSensor {
-    tx: Tx1,
+link::protocol!(
+    /// The temperature protocol as spoken over a [`Link`].
+    TemperatureProtocol,
+    temperature,
+    Kind::Temperature
+);
 
-    transfer_in: TransferIn1,
+pub struct TempSensor {
+    link: Link<TemperatureProtocol, (Tx1, TransferIn1)>,
 
-    signal: SignalReader<'static, ()>,
-    command_buf: CommandBuffer<256>,
+    /// Pushed to the sensor at startup.
+    calibration: Calibration,
+    missed_periods: u32,
 }
 
 impl TempSensor {
-    pub const fn new(tx: Tx1, transfer_in: TransferIn1, signal: SignalReader<'static, ()>) -> Self {
+    pub const fn new(
+        address: Address,
+        tx: Tx1,
+        transfer_in: TransferIn1,
+        signal: SignalReader<'static, ()>,
+        line_rate: LineRate,
+        calibration: Calibration,
+    ) -> Self {
         Self {
-            tx,
-            transfer_in,
+            link: Link::new(address, (tx, transfer_in), signal, line_rate),
 
-            signal,
-            command_buf: CommandBuffer::new(),
+            calibration,
+            missed_periods: 0,
         }
     }
 
-    fn write_command(&mut self, command: ToPeripheral) -> Result<(), Error> {
-        use stm32g4xx_hal::{block, hal::serial::Write as _};
+    pub async fn read_temperature(&mut self) -> Result<Temperature, Error> {
+        // 1. send read command and receive measurement or timeout
+        let temp = match self.link.transact(ToPeripheral::Read).await? {
+            FromPeripheral::Temperature(temp) => temp,
+            FromPeripheral::Fault(fault) => return Err(Error::Fault(fault)),
+            _ => return Err(Error::NonConformance),
+        };
 
-        let mut buf = [0; 8];
-        let mut n = 0;
-        command.serialize_iter(buf.iter_mut().inspect(|_| {
-            n += 1;
-        }))?;
+        fmt::trace!("received temp: {}", temp);
 
-        for byte in &buf[..n] {
-            fmt::unwrap!(block!(self.tx.write(*byte)));
-        }
+        Ok(temp)
+    }
+
+    /// Returns the transaction ID measurements will be
+    /// pushed with, and the period the sensor settled on.
+    pub async fn subscribe(&mut self, period_ms: u16) -> Result<(TransactionId, u16), Error> {
+        // 1. send subscribe command
+        let id = self
+            .link
+            .write_command(ToPeripheral::Subscribe { period_ms })?;
+        fmt::trace!("sent subscribe command");
+
+        // 2. receive acknowledgement or timeout
+        let FromPeripheral::Subscribed { period_ms } =
+            Mono::timeout_after(100u64.millis(), self.link.read_command(id)).await??
+        else {
+            return Err(Error::NonConformance);
+        };
+
+        fmt::info!("subscribed, period: {} ms", period_ms);
+
+        Ok((id, period_ms))
+    }
 
-        fmt::unwrap!(block!(self.tx.flush()));
+    pub async fn unsubscribe(&mut self) -> Result<(), Error> {
+        // 1. send unsubscribe command and receive acknowledgement or timeout
+        let FromPeripheral::Unsubscribed = self.link.transact(ToPeripheral::Unsubscribe).await?
+        else {
+            return Err(Error::NonConformance);
+        };
 
         Ok(())
     }
 
-    async fn read_command(&mut self) -> Result<FromPeripheral, Error> {
-        loop {
-            self.signal.wait().await;
+    /// Wait for the next pushed measurement.
+    ///
+    /// Times out if the period passes without one.
+    async fn next_sample(
+        &mut self,
+        id: TransactionId,
+        period_ms: u16,
+    ) -> Result<Temperature, Error> {
+        // some slack for jitter
+        let deadline = (period_ms as u64 * 3 / 2).millis();
+
+        match Mono::timeout_after(deadline, self.link.read_command(id)).await?? {
+            FromPeripheral::Temperature(temp) => {
+                fmt::trace!("pushed temp: {}", temp);
+
+                Ok(temp)
+            }
+            FromPeripheral::Fault(fault) => Err(Error::Fault(fault)),
+            _ => Err(Error::NonConformance),
+        }
+    }
 
-            self.transfer_in.peek_buffer(|buf, remaining| {
-                fmt::trace!("buf: {}", buf[..buf.len() - remaining]);
+    /// Have the sensor correct its readings with `calibration`.
+    pub async fn calibrate(&mut self, calibration: Calibration) -> Result<(), Error> {
+        // 1. send calibration and validate the calibration in effect
+        match self
+            .link
+            .transact(ToPeripheral::SetCalibration(calibration))
+            .await?
+        {
+            FromPeripheral::Calibration(acknowledged) if acknowledged == calibration => {}
+            _ => return Err(Error::NonConformance),
+        }
 
-                self.command_buf
-                    .ingest(buf[..buf.len() - remaining].iter())?;
+        // 2. read it back to make sure the sensor kept it
+        if self.read_calibration().await? != calibration {
+            return Err(Error::NonConformance);
+        }
 
-                Ok::<_, embedded_command::command_buffer::error::Overflow>(())
-            })?;
+        fmt::info!("calibrated: {}", calibration);
 
-            self.transfer_in.restart(|_| {});
+        Ok(())
+    }
 
-            let mut iter = self.command_buf.iter();
+    pub async fn read_calibration(&mut self) -> Result<Calibration, Error> {
+        // 1. send calibration request and receive calibration or timeout
+        match self.link.transact(ToPeripheral::GetCalibration).await? {
+            FromPeripheral::Calibration(calibration) => Ok(calibration),
+            _ => Err(Error::NonConformance),
+        }
+    }
 
-            let result = FromPeripheral::deserialize_iter(&mut iter);
+    /// Hand a measurement over to the model.
+    fn record(
+        model: &mut impl Mutex<T = Model>,
+        result: Result<Temperature, Error>,
+    ) -> Result<(), Error> {
+        match result {
+            Ok(measurement) => {
+                model.lock(|model| {
+                    model.push_temperature(measurement);
+                });
+            }
+            // a corrupted reply or a busy peripheral
+            // is not fatal, the next cycle will try again
+            Err(Error::Corrupt) => fmt::warn!("dropped corrupt frame"),
+            Err(Error::Nack(reason @ (NackReason::Busy | NackReason::NotReady))) => {
+                fmt::warn!("request refused: {}", reason)
+            }
+            // a faulted reading is no reading at all
+            Err(Error::Fault(fault)) => {
+                fmt::warn!("sensor fault: {}", fault);
 
-            let result = match result {
-                // special case
-                Err(cookie_cutter::error::Error::EndOfInput) => continue,
+                model.lock(|model| {
+                    model.push_missing_temperature();
+                });
+            }
+            Err(e) => return Err(e),
+        }
+
+        Ok(())
+    }
 
-                Ok(cmd) => Ok(cmd),
-                Err(e) => Err(e.into()),
-            };
+    /// Consume pushed measurements until the stream stops.
+    async fn stream(
+        &mut self,
+        model: &mut impl Mutex<T = Model>,
+        id: TransactionId,
+        period_ms: u16,
+    ) -> Result<(), Error> {
+        let mut missed = 0;
+
+        while missed < MAX_MISSED_PERIODS {
+            match self.next_sample(id, period_ms).await {
+                Err(Error::Timeout) => {
+                    missed += 1;
+                    self.missed_periods += 1;
+                    fmt::warn!("missed period ({} total)", self.missed_periods);
+                }
+                result => {
+                    missed = 0;
+                    Self::record(model, result)?;
+                }
+            }
+        }
 
-            let memento = iter.capture();
-            self.command_buf.flush(memento);
+        fmt::warn!("stream stopped, falling back to polling");
 
-            break result;
+        // best effort, the sensor may not be listening
+        match self.unsubscribe().await {
+            Ok(())
+            | Err(Error::Timeout | Error::Corrupt | Error::NonConformance | Error::Nack(_)) => {
+                Ok(())
+            }
+            Err(e) => Err(e),
         }
     }
 
-    pub async fn read_temperature(&mut self) -> Result<Temperature, Error> {
-        // 1. send read command
-        self.write_command(ToPeripheral::Read)?;
-        fmt::trace!("sent read command");
-
-        // 2. receive measurement command or timeout
-        let FromPeripheral::Temperature(temp) =
-            Mono::timeout_after(100u64.millis(), self.read_command()).await??;
+    async fn poll(&mut self, model: &mut impl Mutex<T = Model>, count: u32) -> Result<(), Error> {
+        for _ in 0..count {
+            // 1. fetch latest measurement
+            let result = try_join(self.read_temperature(), async {
+                Mono::delay(1u64.secs()).await;
+                Ok(())
+            })
+            .await;
 
-        fmt::trace!("received temp: {}", temp);
+            // 2. update model
+            Self::record(model, result.map(|(measurement, _)| measurement))?;
+        }
 
-        Ok(temp)
+        Ok(())
     }
 
     pub async fn run(&mut self, mut model: impl Mutex<T = Model>) -> Result<(), Error> {
-        self.transfer_in.start(|_| {});
+        self.link.start();
+
+        self.link.handshake().await?;
+
+        let rate = self.link.negotiate_baud(BAUD_RATE).await?;
+        fmt::info!("link running at {} bps", rate.as_bps());
+
+        let mut attempts = 0;
 
         loop {
-            // 1. fetch latest measurement
-            try_join(self.read_temperature(), async {
-                Mono::delay(1u64.secs()).await;
-                Ok(())
-            })
-            .await
-            .and_then(|(measurement, _)| {
-                // 2. update model
-                model.lock(|model| {
-                    model.push_temperature(measurement);
-                });
+            match self.calibrate(self.calibration).await {
+                Ok(()) => break,
+                // a lost or garbled exchange, as in the handshake
+                Err(
+                    Error::Timeout
+                    | Error::Corrupt
+                    | Error::Nack(NackReason::Busy | NackReason::NotReady),
+                ) if attempts < 10 => {
+                    attempts += 1;
+                    Mono::delay(500u64.millis()).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
 
-                Ok(())
-            })?;
+        loop {
+            // 1. have measurements pushed while the sensor keeps up
+            match self.subscribe(STREAM_PERIOD_MS).await {
+                Ok((id, period_ms)) => self.stream(&mut model, id, period_ms).await?,
+                Err(
+                    Error::Timeout
+                    | Error::Corrupt
+                    | Error::Nack(NackReason::Busy | NackReason::NotReady),
+                ) => fmt::warn!("failed to subscribe"),
+                Err(e) => return Err(e),
+            }
+
+            // 2. poll for a while before trying again
+            self.poll(&mut model, POLLS_BEFORE_RESUBSCRIBE).await?;
         }
     }
 }
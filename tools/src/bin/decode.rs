@@ -0,0 +1,221 @@
+//! Decodes captured traffic of a peripheral link.
+//!
+//! ```text
+//! decode <pump|temperature> [--format list|hex|raw] [FILE]
+//! ```
+//!
+//! Reads from stdin if no file is given. Formats:
+//!
+//! - `list` (default): byte lists as printed by `fmt::trace!("buf: {}", ...)`,
+//!   e.g. `[0, 7, 16, 3, 202, 94]`. Everything outside the brackets is ignored,
+//!   so RTT logs can be fed in as is.
+//! - `hex`: hex digits, optionally separated by whitespace or commas,
+//!   e.g. `00 07 10 03 ca 5e`.
+//! - `raw`: the bytes themselves.
+//!
+//! Every frame is printed with its offset and length, as a request or
+//! a response depending on which one it decodes as. Bytes which are
+//! not part of a valid frame are flagged.
+
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use common::{command, frame};
+use cookie_cutter::SerializeIter;
+
+enum Format {
+    List,
+    Hex,
+    Raw,
+}
+
+const USAGE: &str = "usage: decode <pump|temperature> [--format list|hex|raw] [FILE]";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+
+    let mut link = None;
+    let mut format = Format::List;
+    let mut path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("list") => Format::List,
+                    Some("hex") => Format::Hex,
+                    Some("raw") => Format::Raw,
+                    _ => return usage(),
+                }
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ if link.is_none() => link = Some(arg),
+            _ if path.is_none() => path = Some(arg),
+            _ => return usage(),
+        }
+    }
+
+    let input = match &path {
+        Some(path) => fs::read(path),
+        None => {
+            let mut input = Vec::new();
+            io::stdin().read_to_end(&mut input).map(|_| input)
+        }
+    };
+
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("failed to read input: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let bytes = match format {
+        Format::List => parse_list(&String::from_utf8_lossy(&input)),
+        Format::Hex => parse_hex(&String::from_utf8_lossy(&input)),
+        Format::Raw => Ok(input),
+    };
+
+    let bytes = match bytes {
+        Ok(bytes) => bytes,
+        Err(token) => {
+            eprintln!("not a byte: {token:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match link.as_deref() {
+        Some("pump") => {
+            decode::<command::pump::Request, command::pump::Response>(&bytes);
+        }
+        Some("temperature") => {
+            decode::<command::temperature::Request, command::temperature::Response>(&bytes);
+        }
+        _ => return usage(),
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
+}
+
+/// A decimal or `0x` prefixed hexadecimal byte.
+fn parse_byte(token: &str) -> Result<u8, String> {
+    let parsed = match token.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => token.parse(),
+    };
+
+    parsed.map_err(|_| token.to_owned())
+}
+
+fn parse_list(input: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+
+    for list in input.split('[').skip(1) {
+        let Some((list, _)) = list.split_once(']') else {
+            continue;
+        };
+
+        for token in list.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            bytes.push(parse_byte(token)?);
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn parse_hex(input: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+
+    for token in input.split(|c: char| c.is_whitespace() || c == ',') {
+        let token = token.strip_prefix("0x").unwrap_or(token);
+
+        if token.len() % 2 != 0 {
+            return Err(token.to_owned());
+        }
+
+        for i in (0..token.len()).step_by(2) {
+            let pair = token.get(i..i + 2).ok_or_else(|| token.to_owned())?;
+            bytes.push(u8::from_str_radix(pair, 16).map_err(|_| token.to_owned())?);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// What a frame turned out to be.
+enum Decoded<Req, Resp> {
+    Request(Req),
+    Response(Resp),
+    /// Failed its COBS, length or CRC check.
+    Corrupt,
+    /// Intact, but neither a request nor a response.
+    Unknown,
+    /// The closing delimiter is missing.
+    Incomplete,
+}
+
+fn decode<Req, Resp>(bytes: &[u8])
+where
+    Req: SerializeIter + Display,
+    Resp: SerializeIter + Display,
+{
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let rest = &bytes[offset..];
+
+        let mut iter = rest.iter();
+        let decoded = match frame::decode::<Req>(&mut iter) {
+            Ok(request) => Decoded::Request(request),
+            Err(frame::Error::Deserialize(_)) => match frame::decode::<Resp>(rest) {
+                Ok(response) => Decoded::Response(response),
+                _ => Decoded::Unknown,
+            },
+            Err(frame::Error::Corrupt) => Decoded::Corrupt,
+            Err(frame::Error::EndOfInput) => Decoded::Incomplete,
+        };
+
+        let len = rest.len() - iter.as_slice().len();
+
+        // skip the delimiters, they belong to no frame
+        let skipped = rest.iter().take_while(|b| **b == frame::DELIMITER).count();
+
+        // only delimiters left
+        if skipped == rest.len() {
+            break;
+        }
+
+        // nor does the closing one
+        let end = match rest[..len].last() {
+            Some(&frame::DELIMITER) => len - 1,
+            _ => len,
+        };
+
+        let frame = &rest[skipped..end];
+
+        print!("{:>6} {:>3}  ", offset + skipped, frame.len());
+
+        match decoded {
+            Decoded::Request(request) => println!("-> {request}"),
+            Decoded::Response(response) => println!("<- {response}"),
+            Decoded::Corrupt => println!("!! corrupt: {frame:02x?}"),
+            Decoded::Unknown => println!("!! unknown message: {frame:02x?}"),
+            Decoded::Incomplete => println!("!! incomplete: {frame:02x?}"),
+        }
+
+        offset += len;
+    }
+}
@@ -0,0 +1,78 @@
+//! Prints a JSON description of the peripheral protocols.
+//!
+//! ```text
+//! describe > protocol.json
+//! ```
+//!
+//! The description is generated from the protocol declarations in
+//! `common`, so firmware for real peripherals can be checked against
+//! the same opcodes and payloads main and the dummy use.
+
+use serde::Serialize;
+
+use common::{
+    command::{description::Message, pump, temperature},
+    frame,
+};
+
+#[derive(Serialize)]
+struct Description {
+    frame: Frame,
+    protocols: Protocols,
+}
+
+#[derive(Serialize)]
+struct Frame {
+    delimiter: u8,
+    max_payload: usize,
+    max_frame: usize,
+    encoding: &'static str,
+    envelope: [&'static str; 3],
+    crc: &'static str,
+}
+
+#[derive(Serialize)]
+struct Protocols {
+    pump: Protocol,
+    temperature: Protocol,
+}
+
+#[derive(Serialize)]
+struct Protocol {
+    version: u8,
+    request: [&'static str; 3],
+    response: [&'static str; 3],
+    messages: &'static [Message],
+}
+
+impl Protocol {
+    const fn new(version: u8, messages: &'static [Message]) -> Self {
+        Self {
+            version,
+            request: ["address: u8", "id: u8", "ToPeripheral"],
+            response: ["address: u8", "id: u8", "FromPeripheral"],
+            messages,
+        }
+    }
+}
+
+fn main() {
+    let description = Description {
+        frame: Frame {
+            delimiter: frame::DELIMITER,
+            max_payload: frame::MAX_PAYLOAD,
+            max_frame: frame::MAX_FRAME,
+            encoding: "COBS",
+            envelope: ["length: u8", "payload", "crc: u16 le"],
+            crc: "CRC-16/CCITT-FALSE",
+        },
+        protocols: Protocols {
+            pump: Protocol::new(pump::PROTOCOL_VERSION, pump::MESSAGES),
+            temperature: Protocol::new(temperature::PROTOCOL_VERSION, temperature::MESSAGES),
+        },
+    };
+
+    let json = serde_json::to_string_pretty(&description).expect("description is serializable");
+
+    println!("{json}");
+}